@@ -1,34 +1,108 @@
 // Anthropic API implementation
 use serde_json::json;
-use std::io::{BufRead, Write};
 use std::time::Duration;
 
-use super::Provider;
+use super::{read_stream, send_request, ApiError, DEFAULT_REQUEST_TIMEOUT, Completion, ProgressSink, Provider, ResponseSink, Tee};
+use crate::capabilities::{resolve_sampling, Sampling};
+
+// Used when neither the settings nor the model's defaults give a max_tokens
+const DEFAULT_MAX_TOKENS: u32 = 2048;
+
+// Output token ceilings per model family, matched by prefix (most specific first)
+const MODEL_MAX_TOKENS: &[(&str, u32)] = &[
+    ("claude-opus-4-5", 64000),
+    ("claude-opus-4-1", 32000),
+    ("claude-opus-4", 32000),
+    ("claude-sonnet-4", 64000),
+    ("claude-haiku-4", 64000),
+    ("claude-3-7-sonnet", 64000),
+    ("claude-3-5-sonnet", 8192),
+    ("claude-3-5-haiku", 8192),
+    ("claude-3-opus", 4096),
+    ("claude-3-sonnet", 4096),
+    ("claude-3-haiku", 4096),
+];
+
+// Ceiling assumed for models missing from the table
+const FALLBACK_MAX_TOKENS: u32 = 4096;
+
+// Smallest thinking budget the API accepts; the budget counts toward max_tokens
+const MIN_THINKING_BUDGET: u32 = 1024;
 
 pub struct Anthropic {
     api_key: String,
     base_url: String,
+    sampling: Sampling,
+    extended_thinking: bool,
+    timeout: Option<Duration>,
 }
 
 impl Anthropic {
     pub fn new(api_key: &str, base_url: Option<&str>) -> Self {
-        let url = base_url.unwrap_or("https://api.anthropic.com").to_string();
+        let url = base_url
+            .map(crate::config::normalize_base_url)
+            .unwrap_or_else(|| crate::config::provider_info("Anthropic").map(|info| info.default_endpoint).unwrap_or_default().to_string());
         Self { 
             api_key: api_key.to_string(),
             base_url: url,
+            sampling: Sampling::default(),
+            extended_thinking: false,
+            timeout: Some(DEFAULT_REQUEST_TIMEOUT),
         }
     }
-}
 
-impl Provider for Anthropic {
-    fn send_prompt(&self, prompt: &str, model: &str, stream: bool) -> Result<String, Box<dyn std::error::Error>> {
-        // Create a blocking client with timeout configuration
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(120)) // 2 minute timeout
-            .build()?;
-        
-        // Prepare the JSON payload for Anthropic API
-        let payload = json!({
+    /// Request a specific max_tokens; it is clamped to the model's ceiling when sent
+    pub fn with_max_tokens(mut self, max_tokens: Option<u32>) -> Self {
+        self.sampling.max_tokens = max_tokens;
+        self
+    }
+
+    /// Request a temperature; unset falls back to the model's default
+    pub fn with_temperature(mut self, temperature: Option<f64>) -> Self {
+        self.sampling.temperature = temperature;
+        self
+    }
+
+    /// Ask for extended thinking; the reasoning comes back as `<think>` blocks
+    pub fn with_extended_thinking(mut self, extended_thinking: bool) -> Self {
+        self.extended_thinking = extended_thinking;
+        self
+    }
+
+    /// Limit how long a request may take; None waits indefinitely
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Output token ceiling for the given model
+    pub fn model_max_tokens(model: &str) -> u32 {
+        MODEL_MAX_TOKENS
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, limit)| *limit)
+            .unwrap_or(FALLBACK_MAX_TOKENS)
+    }
+
+    /// The max_tokens value that will be sent for the given model, warning if it was clamped
+    pub fn resolve_max_tokens(&self, model: &str) -> u32 {
+        let requested = resolve_sampling("anthropic", model, self.sampling).max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+        let ceiling = Self::model_max_tokens(model);
+        if requested > ceiling {
+            eprintln!(
+                "Warning: max_tokens {} exceeds the {} limit for {}; clamping to {}",
+                requested, ceiling, model, ceiling
+            );
+            ceiling
+        } else {
+            requested
+        }
+    }
+
+    /// Build the JSON payload sent to the Messages API
+    pub fn build_payload(&self, prompt: &str, model: &str, stream: bool) -> serde_json::Value {
+        let max_tokens = self.resolve_max_tokens(model);
+        let mut payload = json!({
             "model": model,
             "messages": [
                 {
@@ -36,67 +110,105 @@ impl Provider for Anthropic {
                     "content": prompt
                 }
             ],
-            "max_tokens": 2048,
+            "max_tokens": max_tokens,
             "stream": stream
         });
+        if self.extended_thinking {
+            // Half of max_tokens goes to thinking, leaving room for the answer; extended
+            // thinking doesn't accept a temperature
+            let budget = (max_tokens / 2).max(MIN_THINKING_BUDGET);
+            let needed = budget + MIN_THINKING_BUDGET;
+            if max_tokens < needed {
+                eprintln!(
+                    "Warning: max_tokens {} is too small for extended thinking on {}; raising it to {}",
+                    max_tokens, model, needed
+                );
+                payload["max_tokens"] = json!(needed);
+            }
+            payload["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
+        } else if let Some(temperature) = resolve_sampling("anthropic", model, self.sampling).temperature {
+            payload["temperature"] = json!(temperature);
+        }
+        payload
+    }
+}
+
+impl Provider for Anthropic {
+    fn send_prompt(&self, prompt: &str, model: &str, sink: Option<&mut dyn ResponseSink>) -> Result<Completion, Box<dyn std::error::Error>> {
+        // Create a blocking client with timeout configuration
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()?;
         
-        println!("Sending request to Anthropic...");
+        // Prepare the JSON payload for Anthropic API
+        let payload = self.build_payload(prompt, model, sink.is_some());
         
         // Send a POST request to the Anthropic API endpoint
-        let response = client
+        let request = client
             .post(format!("{}/v1/messages", self.base_url))
             .header("X-API-Key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()?;
+            .header("Content-Type", "application/json");
+        let response = send_request("Anthropic", request.json(&payload))?;
         
         // Check if response is successful
         if !response.status().is_success() {
-            return Err(format!("Anthropic API error: {}", response.status()).into());
+            return Err(Box::new(ApiError::from_response("Anthropic", response)));
         }
         
-        let mut full_response = String::new();
-        
-        if stream {
-            // Process the stream line by line
+        if let Some(sink) = sink {
+            // Process the stream line by line; Anthropic prefixes each event with "data: "
             let reader = std::io::BufReader::new(response);
-            
-            for line in reader.lines() {
-                let line = line?;
-                if line.is_empty() || line == "data: [DONE]" {
-                    continue;
+            let mut sink = Tee::new().with(sink).with(ProgressSink::open(payload["max_tokens"].as_u64()));
+            let mut in_thinking = false;
+            let completion = read_stream("Anthropic", reader, Some("data: "), |event, completion| {
+                // Thinking deltas are wrapped in <think> tags so they can be filtered like
+                // inline reasoning
+                if let Some(thinking) = event["delta"]["thinking"].as_str() {
+                    let delta = if in_thinking { thinking.to_string() } else { format!("<think>{}", thinking) };
+                    in_thinking = true;
+                    sink.write(&delta)?;
+                    completion.content.push_str(&delta);
                 }
-                
-                // Anthropic prefixes each line with "data: "
-                if let Some(json_str) = line.strip_prefix("data: ") {
-                    // Parse JSON data
-                    if let Ok(json_response) = serde_json::from_str::<serde_json::Value>(json_str) {
-                        // Extract content from the response
-                        if let Some(delta) = json_response["delta"]["text"].as_str() {
-                            print!("{}", delta);
-                            std::io::stdout().flush()?;
-                            full_response.push_str(delta);
-                        }
-                    }
+                if in_thinking && event["type"] == "content_block_stop" {
+                    in_thinking = false;
+                    sink.write("</think>\n\n")?;
+                    completion.content.push_str("</think>\n\n");
                 }
-            }
-            
-            println!("\n"); // Add a newline at the end
-        } else {
-            // Handle non-streaming response
-            let json_response: serde_json::Value = response.json()?;
-            
-            // Handle the Anthropic response format which has content as an array
-            if let Some(content_array) = json_response["content"].as_array() {
-                for item in content_array {
-                    if let Some(text) = item["text"].as_str() {
-                        full_response.push_str(text);
-                    }
+                // Extract content from the response
+                if let Some(delta) = event["delta"]["text"].as_str() {
+                    sink.write(delta)?;
+                    completion.content.push_str(delta);
+                }
+                // The message_delta event carries the stop reason
+                if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                    completion.finish_reason = Some(reason.to_string());
+                }
+                Ok(())
+            })?;
+            return Ok(completion);
+        }
+        
+        // Handle non-streaming response
+        let json_response: serde_json::Value = response.json()?;
+        let mut full_response = String::new();
+        
+        // Handle the Anthropic response format which has content as an array
+        if let Some(content_array) = json_response["content"].as_array() {
+            for item in content_array {
+                if let Some(thinking) = item["thinking"].as_str().filter(|_| item["type"] == "thinking") {
+                    full_response.push_str(&format!("<think>{}</think>\n\n", thinking));
+                } else if let Some(text) = item["text"].as_str() {
+                    full_response.push_str(text);
                 }
             }
         }
+        let finish_reason = json_response["stop_reason"].as_str().map(str::to_string);
         
-        Ok(full_response)
+        Ok(Completion { content: full_response, finish_reason })
+    }
+
+    fn emits_thinking_blocks(&self) -> bool {
+        self.extended_thinking
     }
 }
\ No newline at end of file
@@ -2,36 +2,44 @@
 use serde_json::json;
 use std::time::Duration;
 
-use super::Provider;
+use super::{send_request, ApiError, DEFAULT_REQUEST_TIMEOUT, Completion, Provider, ResponseSink};
+use crate::capabilities::{resolve_sampling, Sampling};
 
 pub struct Gemini {
     api_key: String,
     base_url: String,
+    sampling: Sampling,
+    timeout: Option<Duration>,
 }
 
 impl Gemini {
     pub fn new(api_key: &str, base_url: Option<&str>) -> Self {
-        let url = base_url.unwrap_or("https://generativelanguage.googleapis.com").to_string();
+        let url = base_url
+            .map(crate::config::normalize_base_url)
+            .unwrap_or_else(|| crate::config::provider_info("Gemini").map(|info| info.default_endpoint).unwrap_or_default().to_string());
         Self { 
             api_key: api_key.to_string(),
             base_url: url,
+            sampling: Sampling::default(),
+            timeout: Some(DEFAULT_REQUEST_TIMEOUT),
         }
     }
-}
 
-impl Provider for Gemini {
-    fn send_prompt(&self, prompt: &str, model: &str, stream: bool) -> Result<String, Box<dyn std::error::Error>> {
-        // Create a blocking client with timeout configuration
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(120)) // 2 minute timeout
-            .build()?;
-        
-        // Create the API endpoint with model and API key
-        let api_url = format!("{}/v1beta/models/{}:generateContent?key={}", 
-            self.base_url, model, self.api_key);
-        
-        // Prepare the JSON payload for Gemini API
-        let payload = json!({
+    /// Request a temperature and max_tokens; unset values fall back to the model's defaults
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Limit how long a request may take; None waits indefinitely
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Build the JSON payload sent to the generateContent API
+    pub fn build_payload(&self, prompt: &str, model: &str) -> serde_json::Value {
+        let mut payload = json!({
             "contents": [
                 {
                     "role": "user",
@@ -41,49 +49,62 @@ impl Provider for Gemini {
                         }
                     ]
                 }
-            ],
-            "generationConfig": {
-                "temperature": 0.7,
-                "maxOutputTokens": 2048
-            }
+            ]
         });
+
+        let sampling = resolve_sampling("gemini", model, self.sampling);
+        let mut generation_config = serde_json::Map::new();
+        if let Some(temperature) = sampling.temperature {
+            generation_config.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(max_tokens) = sampling.max_tokens {
+            generation_config.insert("maxOutputTokens".to_string(), json!(max_tokens));
+        }
+        if !generation_config.is_empty() {
+            payload["generationConfig"] = generation_config.into();
+        }
+        payload
+    }
+}
+
+impl Provider for Gemini {
+    fn send_prompt(&self, prompt: &str, model: &str, _sink: Option<&mut dyn ResponseSink>) -> Result<Completion, Box<dyn std::error::Error>> {
+        // Create a blocking client with timeout configuration
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()?;
         
-        println!("Sending request to Google Gemini...");
+        // Create the API endpoint with model and API key
+        let api_url = format!("{}/v1beta/models/{}:generateContent?key={}", 
+            self.base_url, model, self.api_key);
+        
+        // Prepare the JSON payload for Gemini API
+        let payload = self.build_payload(prompt, model);
         
         // Send a POST request to the Gemini API endpoint
-        let response = client
+        let request = client
             .post(api_url)
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()?;
+            .json(&payload);
+        let response = send_request("Gemini", request)?;
         
         // Check if response is successful
         if !response.status().is_success() {
-            return Err(format!("Gemini API error: {}", response.status()).into());
+            return Err(Box::new(ApiError::from_response("Gemini", response)));
         }
         
         let json_response: serde_json::Value = response.json()?;
         let mut full_response = String::new();
+        let finish_reason = json_response["candidates"][0]["finishReason"].as_str().map(str::to_string);
         
         // Extract text from response
         if let Some(candidates) = json_response["candidates"].as_array() {
             if let Some(candidate) = candidates.first() {
                 if let Some(content) = candidate["content"].as_object() {
                     if let Some(parts) = content["parts"].as_array() {
-                        if stream {
-                            // In streaming mode, print each part as soon as it's processed
-                            for part in parts {
-                                if let Some(text) = part["text"].as_str() {
-                                    println!("{}", text);
-                                    full_response.push_str(text);
-                                }
-                            }
-                        } else {
-                            // Accumulate all text and return at once
-                            for part in parts {
-                                if let Some(text) = part["text"].as_str() {
-                                    full_response.push_str(text);
-                                }
+                        for part in parts {
+                            if let Some(text) = part["text"].as_str() {
+                                full_response.push_str(text);
                             }
                         }
                     }
@@ -91,6 +112,11 @@ impl Provider for Gemini {
             }
         }
         
-        Ok(full_response)
+        Ok(Completion { content: full_response, finish_reason })
+    }
+
+    // generateContent returns the whole response at once
+    fn supports_streaming(&self) -> bool {
+        false
     }
 }
@@ -6,6 +6,13 @@ mod anthropic;
 mod ollama;
 mod gemini;
 
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::capabilities::{capabilities, context_window, Sampling};
+use crate::utils::output::StreamProgress;
+
 // Provider implementations
 pub use openai::OpenAI;
 pub use anthropic::Anthropic;
@@ -14,35 +21,680 @@ pub use gemini::Gemini;
 
 // Trait for API providers
 pub trait Provider {
-    fn send_prompt(&self, prompt: &str, model: &str, stream: bool) -> Result<String, Box<dyn std::error::Error>>;
+    // Send a prompt; with a sink the response is streamed into it as it arrives
+    fn send_prompt(&self, prompt: &str, model: &str, sink: Option<&mut dyn ResponseSink>) -> Result<Completion, Box<dyn std::error::Error>>;
+
+    // Whether the endpoint streams responses; when it doesn't, ApiClient::stream_prompt
+    // makes a regular request and prints the whole response instead
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    // Whether responses carry the model's reasoning as `<think>` blocks regardless of the
+    // model, e.g. because extended thinking was requested
+    fn emits_thinking_blocks(&self) -> bool {
+        false
+    }
+
+    // Context window configured for this provider, e.g. Ollama's `num_ctx`, which takes the
+    // place of the known window of the model
+    fn context_window(&self) -> Option<u64> {
+        None
+    }
+}
+
+// Response text along with the provider's reason for ending it
+#[derive(Debug, Clone, Default)]
+pub struct Completion {
+    pub content: String,
+    // Finish/stop reason as reported by the provider (e.g. "stop", "length", "max_tokens")
+    pub finish_reason: Option<String>,
+}
+
+impl Completion {
+    // Whether the provider stopped because it hit the output token limit
+    pub fn is_truncated(&self) -> bool {
+        self.finish_reason.as_deref().is_some_and(is_truncation_reason)
+    }
+}
+
+// Finish reasons meaning the output token limit cut the response off:
+// OpenAI and Ollama report "length", Anthropic "max_tokens", Gemini "MAX_TOKENS"
+fn is_truncation_reason(reason: &str) -> bool {
+    reason == "length" || reason.eq_ignore_ascii_case("max_tokens")
+}
+
+// Error returned when a provider responds with a non-success HTTP status
+#[derive(Debug)]
+pub struct ApiError {
+    pub provider: String,
+    pub status: u16,
+    pub body: String,
+}
+
+impl ApiError {
+    // Build an error from a failed response, keeping the body for diagnostics
+    pub fn from_response(provider: &str, response: reqwest::blocking::Response) -> Self {
+        let status = response.status().as_u16();
+        let body = response.text().unwrap_or_default();
+        Self { provider: provider.to_string(), status, body }
+    }
+
+    // Whether the failure is transient (rate limits, overload, server errors).
+    // Client errors such as 400/401/403/404 will fail the same way again and are never retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.status, 408 | 429 | 500 | 502 | 503 | 504 | 529)
+    }
+
+    // Targeted advice for failures the user can fix themselves
+    pub fn hint(&self) -> Option<String> {
+        if let Some(model) = self.missing_ollama_model() {
+            return Some(format!(
+                "Ollama doesn't have the model '{}'. Run 'ollama pull {}', or pass --auto-pull to pull it automatically.",
+                model, model
+            ));
+        }
+        match self.status {
+            401 => Some(format!(
+                "{} rejected the API key. Check your API key or run 'ola configure'.",
+                self.provider
+            )),
+            _ => None,
+        }
+    }
+
+    // The model named in Ollama's "model not found, try pulling it first" error, if this is one
+    pub fn missing_ollama_model(&self) -> Option<String> {
+        if self.provider != "Ollama" || self.status != 404 {
+            return None;
+        }
+        let message = serde_json::from_str::<serde_json::Value>(&self.body)
+            .ok()
+            .and_then(|body| body["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| self.body.clone());
+        let re = regex::Regex::new(r#"model ["']([^"']+)["'] not found"#).ok()?;
+        re.captures(&message).map(|captures| captures[1].to_string())
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match reqwest::StatusCode::from_u16(self.status) {
+            Ok(status) => write!(f, "{} API error: {}", self.provider, status)?,
+            Err(_) => write!(f, "{} API error: {}", self.provider, self.status)?,
+        }
+        if !self.body.is_empty() {
+            write!(f, " - {}", self.body)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// Error returned when a provider can't be reached, naming the host and the likely cause
+#[derive(Debug)]
+pub struct NetworkError {
+    pub provider: String,
+    // Host and port the request went to; never the full URL, which can carry an API key
+    pub host: String,
+    pub source: reqwest::Error,
+}
+
+impl NetworkError {
+    pub fn new(provider: &str, source: reqwest::Error) -> Self {
+        let host = source
+            .url()
+            .and_then(|url| url.host_str().map(|host| match url.port_or_known_default() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            }))
+            .unwrap_or_else(|| "the configured base_url".to_string());
+        Self { provider: provider.to_string(), host, source: source.without_url() }
+    }
+
+    // Whether trying again may help: timeouts and failed connections
+    pub fn is_retryable(&self) -> bool {
+        self.source.is_timeout() || self.source.is_connect()
+    }
+
+    // Plain-words cause of a failed connection, with what to check
+    fn connect_cause(&self) -> Option<&'static str> {
+        let mut source = std::error::Error::source(&self.source);
+        while let Some(error) = source {
+            if error.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::ConnectionRefused) {
+                return Some("connection refused. Is the server running, and is the base_url correct?");
+            }
+            let text = error.to_string().to_lowercase();
+            if text.contains("dns error") || text.contains("failed to lookup address") {
+                return Some("host not found. Check the base_url and your network connection.");
+            }
+            if text.contains("certificate") || text.contains("tls") || text.contains("ssl") {
+                return Some("TLS handshake failed. Check that the base_url uses the right scheme (http or https).");
+            }
+            source = error.source();
+        }
+        None
+    }
+}
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.source.is_timeout() {
+            return write!(
+                f,
+                "{} at {} did not respond in time; raise behavior.request_timeout_secs or pass --timeout",
+                self.provider, self.host
+            );
+        }
+        match self.connect_cause() {
+            Some(cause) => write!(f, "Could not open a connection to {} at {}: {}", self.provider, self.host, cause),
+            None if self.source.is_connect() => write!(f, "Could not open a connection to {} at {}: {}", self.provider, self.host, self.source),
+            None => write!(f, "Request to {} at {} failed: {}", self.provider, self.host, self.source),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+// Send a provider request, describing a failure to reach it as a NetworkError
+pub(crate) fn send_request(provider: &str, request: reqwest::blocking::RequestBuilder) -> Result<reqwest::blocking::Response, NetworkError> {
+    request.send().map_err(|e| NetworkError::new(provider, e))
+}
+
+// Error returned when a streamed response breaks off part-way; the text streamed
+// before the failure is kept in `partial`
+#[derive(Debug)]
+pub struct StreamError {
+    pub provider: String,
+    pub partial: Completion,
+    pub source: std::io::Error,
+}
+
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} stream interrupted after {} characters: {}",
+            self.provider,
+            self.partial.content.chars().count(),
+            self.source
+        )
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+// Destination for streamed response text; deltas are written as they arrive
+pub trait ResponseSink {
+    fn write(&mut self, delta: &str) -> std::io::Result<()>;
+}
+
+impl<S: ResponseSink + ?Sized> ResponseSink for &mut S {
+    fn write(&mut self, delta: &str) -> std::io::Result<()> {
+        (**self).write(delta)
+    }
+}
+
+// Writes deltas to an output such as stdout, flushing after each one so they show up right away
+pub struct WriterSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl WriterSink<std::io::Stdout> {
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+}
+
+impl<W: Write> ResponseSink for WriterSink<W> {
+    fn write(&mut self, delta: &str) -> std::io::Result<()> {
+        self.out.write_all(delta.as_bytes())?;
+        self.out.flush()
+    }
+}
+
+// Appends deltas to a file as they arrive (--stream-to-file), so an interrupted run still
+// leaves the partial response behind. Failing to open or write the file is reported once
+// and only stops the file writes, never the stream.
+pub struct FileSink {
+    path: PathBuf,
+    file: Option<std::fs::File>,
+}
+
+impl FileSink {
+    // Open the file for appending, creating parent directories
+    pub fn open(path: &Path) -> Self {
+        let open = || -> std::io::Result<std::fs::File> {
+            if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::OpenOptions::new().create(true).append(true).open(path)
+        };
+        let file = match open() {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("Warning: could not open {} to stream into ({}); only printing the response", path.display(), e);
+                None
+            }
+        };
+        Self { path: path.to_path_buf(), file }
+    }
+}
+
+impl ResponseSink for FileSink {
+    fn write(&mut self, delta: &str) -> std::io::Result<()> {
+        if let Some(file) = &mut self.file {
+            if let Err(e) = file.write_all(delta.as_bytes()).and_then(|_| file.flush()) {
+                eprintln!("Warning: writing to {} failed ({}); no longer streaming into it", self.path.display(), e);
+                self.file = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+// With a known max_tokens, draws generation progress on stderr when it is a terminal and
+// the response itself is redirected, so the bar never lands in the middle of the text.
+// The bar is cleared once the stream ends, whether or not it completed.
+pub(crate) struct ProgressSink {
+    progress: Option<StreamProgress<std::io::Stderr>>,
+}
+
+impl ProgressSink {
+    pub(crate) fn open(max_tokens: Option<u64>) -> Self {
+        let show_progress = crate::utils::output::stderr_is_tty() && !atty::is(atty::Stream::Stdout);
+        let progress = max_tokens
+            .filter(|_| show_progress)
+            .map(|max_tokens| StreamProgress::new(std::io::stderr(), max_tokens));
+        Self { progress }
+    }
+}
+
+impl ResponseSink for ProgressSink {
+    fn write(&mut self, delta: &str) -> std::io::Result<()> {
+        match &mut self.progress {
+            Some(progress) => progress.update(delta),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ProgressSink {
+    fn drop(&mut self) {
+        if let Some(progress) = self.progress.take() {
+            let _ = progress.finish();
+        }
+    }
+}
+
+// Fans each delta out to every sink in turn, so the response is only walked once however
+// many outputs it goes to
+#[derive(Default)]
+pub struct Tee<'a> {
+    sinks: Vec<Box<dyn ResponseSink + 'a>>,
+}
+
+impl<'a> Tee<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Add a sink; borrowed sinks (`&mut sink`) can be read back once the stream is done
+    pub fn with(mut self, sink: impl ResponseSink + 'a) -> Self {
+        self.push(sink);
+        self
+    }
+
+    pub fn push(&mut self, sink: impl ResponseSink + 'a) {
+        self.sinks.push(Box::new(sink));
+    }
+}
+
+impl ResponseSink for Tee<'_> {
+    fn write(&mut self, delta: &str) -> std::io::Result<()> {
+        for sink in &mut self.sinks {
+            sink.write(delta)?;
+        }
+        Ok(())
+    }
+}
+
+// Read a streamed response line by line, handing each JSON event to `on_event`.
+// With a `data_prefix` (server-sent events), lines without it are ignored.
+// Lines that aren't valid JSON are skipped, with a note on stderr when OLA_DEBUG is set;
+// a read error part-way through becomes a StreamError holding what was received so far.
+fn read_stream<R, F>(provider: &str, reader: R, data_prefix: Option<&str>, mut on_event: F) -> Result<Completion, Box<dyn std::error::Error>>
+where
+    R: BufRead,
+    F: FnMut(&serde_json::Value, &mut Completion) -> std::io::Result<()>,
+{
+    let mut completion = Completion::default();
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(source) => {
+                return Err(Box::new(StreamError { provider: provider.to_string(), partial: completion, source }));
+            }
+        };
+        let data = match data_prefix {
+            Some(prefix) => match line.strip_prefix(prefix) {
+                Some(data) => data,
+                None => continue,
+            },
+            None => line.as_str(),
+        };
+        if data.trim().is_empty() || data == "[DONE]" {
+            continue;
+        }
+        match serde_json::from_str::<serde_json::Value>(data) {
+            Ok(event) => on_event(&event, &mut completion)?,
+            Err(e) => {
+                if std::env::var_os("OLA_DEBUG").is_some() {
+                    eprintln!("Debug: skipped unparseable {} stream line ({}): {}", provider, e, data);
+                }
+            }
+        }
+    }
+    Ok(completion)
+}
+
+// Whether an error from a provider call is worth retrying with another model
+pub fn is_retryable_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    if let Some(api_error) = error.downcast_ref::<ApiError>() {
+        return api_error.is_retryable();
+    }
+    if let Some(network_error) = error.downcast_ref::<NetworkError>() {
+        return network_error.is_retryable();
+    }
+    if let Some(http_error) = error.downcast_ref::<reqwest::Error>() {
+        return http_error.is_timeout() || http_error.is_connect();
+    }
+    false
+}
+
+// Retry behaviour for transient provider failures
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    // Retries after the first attempt
+    pub max_retries: u32,
+    // Delay before the first retry; doubled for each further retry
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub const DEFAULT_MAX_RETRIES: u32 = 2;
+    pub const DEFAULT_BASE_DELAY_MS: u64 = 1000;
+
+    // Build the policy from request options, using the defaults for unset values
+    pub fn from_options(options: &RequestOptions) -> Self {
+        Self {
+            max_retries: options.max_retries.unwrap_or(Self::DEFAULT_MAX_RETRIES),
+            base_delay: Duration::from_millis(options.retry_delay_ms.unwrap_or(Self::DEFAULT_BASE_DELAY_MS)),
+        }
+    }
+}
+
+// Run a provider call, retrying retryable failures with exponential backoff.
+// Non-retryable failures (bad request, bad key, missing model) are returned immediately.
+pub fn with_retries<T, F>(policy: &RetryPolicy, mut call: F) -> Result<T, Box<dyn std::error::Error>>
+where
+    F: FnMut() -> Result<T, Box<dyn std::error::Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        match call() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_retries && is_retryable_error(e.as_ref()) => {
+                let delay = policy.base_delay * 2u32.saturating_pow(attempt);
+                attempt += 1;
+                eprintln!(
+                    "Warning: request failed ({}); retrying in {}ms ({}/{})",
+                    e, delay.as_millis(), attempt, policy.max_retries
+                );
+                std::thread::sleep(delay);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// How long a request may take when `request_timeout_secs` isn't set
+pub(crate) const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Optional per-request tuning read from the provider's additional_settings
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f64>,
+    pub max_retries: Option<u32>,
+    pub retry_delay_ms: Option<u64>,
+    pub reasoning_effort: Option<String>,
+    // Set to false for OpenAI-compatible endpoints that don't support streaming
+    pub stream: Option<bool>,
+    // Pull a missing Ollama model and retry instead of failing
+    pub auto_pull: bool,
+    // File each streamed delta is appended to as it arrives (--stream-to-file)
+    pub stream_file: Option<PathBuf>,
+    // Ask Anthropic models for extended thinking (--extended-thinking)
+    pub extended_thinking: bool,
+    // Context window for Ollama models, sent as `num_ctx`
+    pub num_ctx: Option<u64>,
+    // Seconds a request may take; 0 means no limit, unset uses DEFAULT_REQUEST_TIMEOUT
+    pub request_timeout_secs: Option<u64>,
+}
+
+impl RequestOptions {
+    // Extract request options from a provider's additional_settings
+    pub fn from_additional_settings(settings: Option<&serde_json::Value>) -> Self {
+        let max_tokens = settings
+            .and_then(|s| s.get("max_tokens"))
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(u32::MAX as u64) as u32);
+        let temperature = settings
+            .and_then(|s| s.get("temperature"))
+            .and_then(|v| v.as_f64());
+        let max_retries = settings
+            .and_then(|s| s.get("max_retries"))
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(u32::MAX as u64) as u32);
+        let retry_delay_ms = settings
+            .and_then(|s| s.get("retry_delay_ms"))
+            .and_then(|v| v.as_u64());
+        let stream = settings
+            .and_then(|s| s.get("stream"))
+            .and_then(|v| v.as_bool());
+        let auto_pull = settings
+            .and_then(|s| s.get("auto_pull"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let stream_file = settings
+            .and_then(|s| s.get("stream_to_file"))
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from);
+        let extended_thinking = settings
+            .and_then(|s| s.get("extended_thinking"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let num_ctx = settings
+            .and_then(|s| s.get("num_ctx"))
+            .and_then(|v| v.as_u64());
+        let request_timeout_secs = settings
+            .and_then(|s| s.get("request_timeout_secs"))
+            .and_then(|v| v.as_u64());
+        Self { max_tokens, temperature, max_retries, retry_delay_ms, reasoning_effort: None, stream, auto_pull, stream_file, extended_thinking, num_ctx, request_timeout_secs }
+    }
+
+    // Fill in temperature and max_tokens the provider leaves unset from `defaults` in the settings
+    pub fn with_setting_defaults(mut self, defaults: &crate::settings::DefaultSettings) -> Self {
+        self.temperature = self.temperature.or(defaults.temperature);
+        self.max_tokens = self.max_tokens.or(defaults.max_tokens);
+        self
+    }
+
+    // Request timeout to build the HTTP client with; None means no limit
+    pub fn timeout(&self) -> Option<Duration> {
+        match self.request_timeout_secs {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => Some(DEFAULT_REQUEST_TIMEOUT),
+        }
+    }
+
+    // Requested sampling; the model's own defaults fill the gaps when the payload is built
+    pub fn sampling(&self) -> Sampling {
+        Sampling { temperature: self.temperature, max_tokens: self.max_tokens }
+    }
 }
 
 // API client for handling communication with LLM providers
 pub struct ApiClient {
     provider: Box<dyn Provider>,
+    provider_name: String,
+    retry: RetryPolicy,
+    stream_file: Option<PathBuf>,
 }
 
 impl ApiClient {
-    // Create a new API client for the specified provider
+    // Create a new API client for the specified provider.
+    // Kept for library users; the binary always passes request options.
+    #[allow(dead_code)]
     pub fn new(provider_name: &str, api_key: &str, base_url: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_options(provider_name, api_key, base_url, &RequestOptions::default())
+    }
+
+    // Create a new API client with explicit request options
+    pub fn with_options(provider_name: &str, api_key: &str, base_url: Option<&str>, options: &RequestOptions) -> Result<Self, Box<dyn std::error::Error>> {
         let provider: Box<dyn Provider> = match provider_name {
-            "OpenAI" => Box::new(OpenAI::new(api_key, base_url)),
-            "Anthropic" => Box::new(Anthropic::new(api_key, base_url)),
-            "Ollama" => Box::new(Ollama::new(base_url)),
-            "Gemini" => Box::new(Gemini::new(api_key, base_url)),
+            "OpenAI" => Box::new(
+                OpenAI::new(api_key, base_url)
+                    .with_reasoning_effort(options.reasoning_effort.clone())
+                    .with_streaming(options.stream.unwrap_or(true))
+                    .with_sampling(options.sampling())
+                    .with_timeout(options.timeout()),
+            ),
+            "Anthropic" => Box::new(
+                Anthropic::new(api_key, base_url)
+                    .with_max_tokens(options.max_tokens)
+                    .with_temperature(options.temperature)
+                    .with_extended_thinking(options.extended_thinking)
+                    .with_timeout(options.timeout()),
+            ),
+            "Ollama" => Box::new(
+                Ollama::new(base_url)
+                    .with_auto_pull(options.auto_pull)
+                    .with_sampling(options.sampling())
+                    .with_num_ctx(options.num_ctx)
+                    .with_timeout(options.timeout()),
+            ),
+            "Gemini" => Box::new(
+                Gemini::new(api_key, base_url)
+                    .with_sampling(options.sampling())
+                    .with_timeout(options.timeout()),
+            ),
             _ => return Err(format!("Unsupported provider: {}", provider_name).into()),
         };
+        if options.reasoning_effort.is_some() && provider_name != "OpenAI" {
+            eprintln!("Warning: {} does not support reasoning_effort; ignoring it", provider_name);
+        }
+        if options.extended_thinking && provider_name != "Anthropic" {
+            eprintln!("Warning: {} does not support extended thinking; ignoring it", provider_name);
+        }
         
-        Ok(Self { provider })
+        Ok(Self::from_provider(provider_name, provider, options))
+    }
+
+    // Create a client around any provider implementation
+    pub fn from_provider(provider_name: &str, provider: Box<dyn Provider>, options: &RequestOptions) -> Self {
+        Self {
+            provider,
+            provider_name: provider_name.to_string(),
+            retry: RetryPolicy::from_options(options),
+            stream_file: options.stream_file.clone(),
+        }
+    }
+
+    // Name of the provider this client talks to
+    pub fn provider_name(&self) -> &str {
+        &self.provider_name
+    }
+
+    // Context window of the given model in tokens: the provider's configured one, or else
+    // the model's known window
+    pub fn context_window(&self, model: &str) -> Option<u64> {
+        self.provider.context_window().or_else(|| context_window(&self.provider_name, model))
+    }
+
+    // Whether responses from the given model can contain `<think>` blocks to filter
+    pub fn visible_thinking(&self, model: &str) -> bool {
+        capabilities(&self.provider_name, model).visible_thinking || self.provider.emits_thinking_blocks()
     }
     
-    // Send a prompt and stream the response
-    pub fn stream_prompt(&self, prompt: &str, model: &str) -> Result<String, Box<dyn std::error::Error>> {
-        self.provider.send_prompt(prompt, model, true)
+    // Send a prompt and print the response as it streams, also appending it to the
+    // --stream-to-file file if one is set
+    pub fn stream_prompt(&self, prompt: &str, model: &str) -> Result<Completion, Box<dyn std::error::Error>> {
+        let mut sink = Tee::new().with(WriterSink::stdout());
+        if let Some(file) = self.file_sink() {
+            sink.push(file);
+        }
+        let completion = self.stream_prompt_to(prompt, model, &mut sink)?;
+        println!("\n");
+        Ok(completion)
+    }
+
+    // Send a prompt and stream the response into `sink`; when the provider can't stream,
+    // the whole response is written to it once complete
+    pub fn stream_prompt_to(&self, prompt: &str, model: &str, sink: &mut dyn ResponseSink) -> Result<Completion, Box<dyn std::error::Error>> {
+        if self.provider.supports_streaming() {
+            return self.request(prompt, model, Some(sink));
+        }
+        let completion = self.request(prompt, model, None)?;
+        sink.write(&completion.content)?;
+        Ok(completion)
+    }
+
+    // Sink for the --stream-to-file file, if one is set
+    pub fn file_sink(&self) -> Option<FileSink> {
+        self.stream_file.as_deref().map(FileSink::open)
+    }
+
+    // Send a prompt and wait for the complete response without printing it
+    pub fn send_prompt(&self, prompt: &str, model: &str) -> Result<Completion, Box<dyn std::error::Error>> {
+        self.request(prompt, model, None)
+    }
+
+    // Send a prompt with retries, pointing out fixable failures such as a bad API key
+    fn request(&self, prompt: &str, model: &str, mut sink: Option<&mut dyn ResponseSink>) -> Result<Completion, Box<dyn std::error::Error>> {
+        let result = with_retries(&self.retry, || {
+            let sink = sink.as_mut().map(|sink| &mut **sink as &mut dyn ResponseSink);
+            self.provider.send_prompt(prompt, model, sink)
+        });
+        if let Err(e) = &result {
+            if let Some(hint) = e.downcast_ref::<ApiError>().and_then(ApiError::hint) {
+                eprintln!("{}", hint);
+            }
+        }
+        result
     }
 }
 
-// Factory function to create an API client from configuration
+// Factory function to create an API client from configuration.
+// Kept for library users; the binary picks the provider from PromptOptions.
+#[allow(dead_code)]
 pub fn create_api_client_from_config() -> Result<ApiClient, Box<dyn std::error::Error>> {
     // Load configuration
     let config = crate::config::Config::load()?;
@@ -53,6 +705,22 @@ pub fn create_api_client_from_config() -> Result<ApiClient, Box<dyn std::error::
         )
     })?;
     
+    create_api_client(&provider_config)
+}
+
+// Create an API client for a specific configured provider
+pub fn create_api_client(provider_config: &crate::config::ProviderConfig) -> Result<ApiClient, Box<dyn std::error::Error>> {
+    let settings = crate::settings::Settings::load().unwrap_or_default();
+    create_api_client_with_effort(provider_config, None, &settings)
+}
+
+// Create an API client for a configured provider, requesting the given reasoning effort
+// and filling unset sampling options and the request timeout from the settings
+pub fn create_api_client_with_effort(
+    provider_config: &crate::config::ProviderConfig,
+    reasoning_effort: Option<&str>,
+    settings: &crate::settings::Settings,
+) -> Result<ApiClient, Box<dyn std::error::Error>> {
     // Extract provider information
     let provider_name = &provider_config.provider;
     let api_key = &provider_config.api_key;
@@ -61,36 +729,57 @@ pub fn create_api_client_from_config() -> Result<ApiClient, Box<dyn std::error::
     let base_url = provider_config.additional_settings.as_ref()
         .and_then(|settings| settings.get("base_url"))
         .and_then(|url| url.as_str());
+    let mut options = RequestOptions::from_additional_settings(provider_config.additional_settings.as_ref())
+        .with_setting_defaults(&settings.defaults);
+    options.request_timeout_secs = options.request_timeout_secs.or(Some(settings.behavior.request_timeout_secs));
+    options.reasoning_effort = reasoning_effort.map(str::to_string);
     
     // Create and return the API client
-    ApiClient::new(provider_name, api_key, base_url)
+    ApiClient::with_options(provider_name, api_key, base_url, &options)
 }
 
 // Helper function to format a prompt with context
 pub fn format_prompt(goals: &str, return_type: &str, warnings: &str, context: Option<&str>) -> String {
     // Try to load settings for custom prefixes
     let settings = crate::settings::Settings::load().unwrap_or_default();
-    
+    format_prompt_with_template(&settings.prompt_template, goals, return_type, warnings, context)
+}
+
+// Format a prompt using the prefixes from an already-loaded prompt template
+pub fn format_prompt_with_template(
+    template: &crate::settings::PromptTemplate,
+    goals: &str,
+    return_type: &str,
+    warnings: &str,
+    context: Option<&str>,
+) -> String {
     // Use custom prefixes from settings if available
-    let goals_prefix = &settings.prompt_template.goals_prefix;
-    let return_format_prefix = &settings.prompt_template.return_format_prefix;
-    let warnings_prefix = &settings.prompt_template.warnings_prefix;
+    let goals_prefix = &template.goals_prefix;
+    let return_format_prefix = &template.return_format_prefix;
+    let warnings_prefix = &template.warnings_prefix;
     
     // Build the input data with optional context
-    if let Some(ctx) = context {
-        format!(
-            "{}{}\n{}{}\n{}{}\nContext: {}",
-            goals_prefix, goals, 
-            return_format_prefix, return_type, 
-            warnings_prefix, warnings, 
-            ctx
-        )
+    let sections = format!(
+        "{}{}\n{}{}\n{}{}",
+        goals_prefix, goals, 
+        return_format_prefix, return_type, 
+        warnings_prefix, warnings
+    );
+    match context {
+        Some(ctx) => format!("{}{}", sections, format_context_block(template, &template.context_label, ctx)),
+        None => sections,
+    }
+}
+
+// Set context apart from the text before it: a blank line, then the label on its own
+// line and the context, wrapped in the template's separator lines
+pub fn format_context_block(template: &crate::settings::PromptTemplate, label: &str, context: &str) -> String {
+    let separator = template.context_separator.trim();
+    let label = label.trim_end();
+    let body = if label.is_empty() { context.to_string() } else { format!("{}\n{}", label, context) };
+    if separator.is_empty() {
+        format!("\n\n{}", body)
     } else {
-        format!(
-            "{}{}\n{}{}\n{}{}",
-            goals_prefix, goals, 
-            return_format_prefix, return_type, 
-            warnings_prefix, warnings
-        )
+        format!("\n\n{}\n{}\n{}", separator, body, separator)
     }
 }
\ No newline at end of file
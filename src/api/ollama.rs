@@ -1,79 +1,164 @@
 // Ollama API implementation
 use serde_json::json;
-use std::io::{BufRead, Write};
 use std::time::Duration;
 
-use super::Provider;
+use super::{read_stream, send_request, ApiError, DEFAULT_REQUEST_TIMEOUT, Completion, ProgressSink, Provider, ResponseSink, Tee};
+use crate::capabilities::{resolve_sampling, Sampling};
 
 pub struct Ollama {
     base_url: String,
+    auto_pull: bool,
+    sampling: Sampling,
+    num_ctx: Option<u64>,
+    timeout: Option<Duration>,
 }
 
 impl Ollama {
     pub fn new(base_url: Option<&str>) -> Self {
-        let url = base_url.unwrap_or("http://localhost:11434").to_string();
-        Self { base_url: url }
+        let url = base_url
+            .map(crate::config::normalize_base_url)
+            .unwrap_or_else(|| crate::config::provider_info("Ollama").map(|info| info.default_endpoint).unwrap_or_default().to_string());
+        Self { base_url: url, auto_pull: false, sampling: Sampling::default(), num_ctx: None, timeout: Some(DEFAULT_REQUEST_TIMEOUT) }
+    }
+
+    /// Pull the model and retry when Ollama reports that it isn't available locally
+    pub fn with_auto_pull(mut self, auto_pull: bool) -> Self {
+        self.auto_pull = auto_pull;
+        self
+    }
+
+    /// Request a temperature and max_tokens; unset values fall back to the model's defaults
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Set the context window; without one Ollama uses its default and silently drops
+    /// the start of longer prompts
+    pub fn with_num_ctx(mut self, num_ctx: Option<u64>) -> Self {
+        self.num_ctx = num_ctx;
+        self
+    }
+
+    /// Limit how long a request may take; None waits indefinitely
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Build the JSON payload sent to the generate API
+    pub fn build_payload(&self, prompt: &str, model: &str, stream: bool) -> serde_json::Value {
+        let mut payload = json!({
+            "model": model,
+            "prompt": prompt,
+            "stream": stream,
+            "options": {}
+        });
+
+        let sampling = resolve_sampling("ollama", model, self.sampling);
+        if let Some(temperature) = sampling.temperature {
+            payload["options"]["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = sampling.max_tokens {
+            payload["options"]["num_predict"] = json!(max_tokens);
+        }
+        if let Some(num_ctx) = self.num_ctx {
+            payload["options"]["num_ctx"] = json!(num_ctx);
+        }
+        payload
+    }
+
+    /// Download a model through `/api/pull`, reporting progress on stderr
+    pub fn pull_model(&self, model: &str) -> Result<(), Box<dyn std::error::Error>> {
+        // Large models take a while to download, so no overall timeout
+        let client = reqwest::blocking::Client::builder()
+            .timeout(None)
+            .build()?;
+        let request = client
+            .post(format!("{}/api/pull", self.base_url))
+            .json(&json!({ "model": model, "stream": true }));
+        let response = send_request("Ollama", request)?;
+        if !response.status().is_success() {
+            return Err(Box::new(ApiError::from_response("Ollama", response)));
+        }
+
+        eprintln!("Pulling {} from the Ollama library...", model);
+        // Progress events repeat their status with byte counts; print each status once
+        let mut last_status = String::new();
+        let reader = std::io::BufReader::new(response);
+        read_stream("Ollama", reader, None, |event, _| {
+            if let Some(error) = event["error"].as_str() {
+                return Err(std::io::Error::other(format!("pulling {} failed: {}", model, error)));
+            }
+            if let Some(status) = event["status"].as_str() {
+                if status != last_status {
+                    eprintln!("  {}", status);
+                    last_status = status.to_string();
+                }
+            }
+            Ok(())
+        })?;
+        Ok(())
     }
 }
 
 impl Provider for Ollama {
-    fn send_prompt(&self, prompt: &str, model: &str, stream: bool) -> Result<String, Box<dyn std::error::Error>> {
+    fn send_prompt(&self, prompt: &str, model: &str, sink: Option<&mut dyn ResponseSink>) -> Result<Completion, Box<dyn std::error::Error>> {
         // Create a blocking client with timeout configuration
         let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(120)) // 2 minute timeout
+            .timeout(self.timeout)
             .build()?;
         
         // Prepare the JSON payload for Ollama API
-        let payload = json!({
-            "model": model,
-            "prompt": prompt,
-            "stream": stream,  // Enable streaming
-            "options": {
-                "num_predict": 2048  // Limit token output
-            }
-        });
-        
-        println!("Sending request to Ollama...");
+        let payload = self.build_payload(prompt, model, sink.is_some());
         
         // Send a POST request to the Ollama API endpoint
-        let response = client
-            .post(format!("{}/api/generate", self.base_url))
-            .json(&payload)
-            .send()?;
+        let generate = || {
+            let request = client
+                .post(format!("{}/api/generate", self.base_url))
+                .json(&payload);
+            send_request("Ollama", request)
+        };
+        let mut response = generate()?;
         
-        // Check if response is successful
+        // Check if response is successful; a model that was never pulled can be pulled and retried
         if !response.status().is_success() {
-            return Err(format!("Ollama API error: {}", response.status()).into());
+            let error = ApiError::from_response("Ollama", response);
+            match error.missing_ollama_model() {
+                Some(missing) if self.auto_pull => {
+                    self.pull_model(&missing)?;
+                    response = generate()?;
+                    if !response.status().is_success() {
+                        return Err(Box::new(ApiError::from_response("Ollama", response)));
+                    }
+                }
+                _ => return Err(Box::new(error)),
+            }
         }
         
-        let mut full_response = String::new();
-        
-        // Process the stream line by line
+        // Process the stream line by line; each line is a JSON object
         let reader = std::io::BufReader::new(response);
-        
-        for line in reader.lines() {
-            let line = line?;
-            if line.is_empty() {
-                continue;
-            }
-            
-            // Parse each line as JSON
-            let json_response: serde_json::Value = serde_json::from_str(&line)?;
-            
+        let max_tokens = payload["options"]["num_predict"].as_u64();
+        let mut sink = sink.map(|sink| Tee::new().with(sink).with(ProgressSink::open(max_tokens)));
+        let completion = read_stream("Ollama", reader, None, |event, completion| {
             // Extract the response text
-            if let Some(text) = json_response["response"].as_str() {
-                if stream {
-                    print!("{}", text);
-                    std::io::stdout().flush()?;
+            if let Some(text) = event["response"].as_str() {
+                if let Some(sink) = &mut sink {
+                    sink.write(text)?;
                 }
-                full_response.push_str(text);
+                completion.content.push_str(text);
             }
-        }
-        
-        if stream {
-            println!("\n"); // Add a newline at the end
-        }
+            // The final object reports why generation stopped
+            if let Some(reason) = event["done_reason"].as_str() {
+                completion.finish_reason = Some(reason.to_string());
+            }
+            Ok(())
+        })?;
         
-        Ok(full_response)
+        Ok(completion)
+    }
+
+    fn context_window(&self) -> Option<u64> {
+        self.num_ctx
     }
 }
\ No newline at end of file
@@ -1,34 +1,61 @@
 // OpenAI API implementation
 use serde_json::json;
-use std::io::{BufRead, Write};
 use std::time::Duration;
 
-use super::Provider;
+use super::{read_stream, send_request, ApiError, DEFAULT_REQUEST_TIMEOUT, Completion, ProgressSink, Provider, ResponseSink, Tee};
+use crate::capabilities::{capabilities, resolve_sampling, Sampling};
 
 pub struct OpenAI {
     api_key: String,
     base_url: String,
+    reasoning_effort: Option<String>,
+    streaming: bool,
+    sampling: Sampling,
+    timeout: Option<Duration>,
 }
 
 impl OpenAI {
     pub fn new(api_key: &str, base_url: Option<&str>) -> Self {
-        let url = base_url.unwrap_or("https://api.openai.com").to_string();
+        let url = base_url
+            .map(crate::config::normalize_base_url)
+            .unwrap_or_else(|| crate::config::provider_info("OpenAI").map(|info| info.default_endpoint).unwrap_or_default().to_string());
         Self { 
             api_key: api_key.to_string(),
             base_url: url,
+            reasoning_effort: None,
+            streaming: true,
+            sampling: Sampling::default(),
+            timeout: Some(DEFAULT_REQUEST_TIMEOUT),
         }
     }
-}
 
-impl Provider for OpenAI {
-    fn send_prompt(&self, prompt: &str, model: &str, stream: bool) -> Result<String, Box<dyn std::error::Error>> {
-        // Create a blocking client with timeout configuration
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(120)) // 2 minute timeout
-            .build()?;
-        
-        // Prepare the JSON payload for OpenAI API
-        let payload = json!({
+    /// Request a reasoning effort level; it is only sent to models that accept one
+    pub fn with_reasoning_effort(mut self, reasoning_effort: Option<String>) -> Self {
+        self.reasoning_effort = reasoning_effort;
+        self
+    }
+
+    /// Mark the endpoint as unable to stream, for OpenAI-compatible servers without SSE
+    pub fn with_streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+
+    /// Request a temperature and max_tokens; unset values fall back to the model's defaults
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Limit how long a request may take; None waits indefinitely
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Build the JSON payload sent to the Chat Completions API
+    pub fn build_payload(&self, prompt: &str, model: &str, stream: bool) -> serde_json::Value {
+        let mut payload = json!({
             "model": model,
             "messages": [
                 {
@@ -38,58 +65,84 @@ impl Provider for OpenAI {
             ],
             "stream": stream
         });
+
+        let sampling = resolve_sampling("openai", model, self.sampling);
+        if let Some(temperature) = sampling.temperature {
+            payload["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = sampling.max_tokens {
+            // Reasoning models, which take no temperature, only accept max_completion_tokens
+            let key = if capabilities("openai", model).temperature { "max_tokens" } else { "max_completion_tokens" };
+            payload[key] = json!(max_tokens);
+        }
+
+        if let Some(effort) = &self.reasoning_effort {
+            if capabilities("openai", model).reasoning_effort {
+                payload["reasoning_effort"] = json!(effort);
+            } else {
+                eprintln!("Warning: {} does not support reasoning_effort; ignoring it", model);
+            }
+        }
+        payload
+    }
+}
+
+impl Provider for OpenAI {
+    fn send_prompt(&self, prompt: &str, model: &str, sink: Option<&mut dyn ResponseSink>) -> Result<Completion, Box<dyn std::error::Error>> {
+        // Create a blocking client with timeout configuration
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()?;
         
-        println!("Sending request to OpenAI...");
+        // Prepare the JSON payload for OpenAI API
+        let payload = self.build_payload(prompt, model, sink.is_some());
         
         // Send a POST request to the OpenAI API endpoint
-        let response = client
+        let request = client
             .post(format!("{}/v1/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()?;
+            .json(&payload);
+        let response = send_request("OpenAI", request)?;
         
         // Check if response is successful
         if !response.status().is_success() {
-            return Err(format!("OpenAI API error: {}", response.status()).into());
+            return Err(Box::new(ApiError::from_response("OpenAI", response)));
         }
         
-        let mut full_response = String::new();
-        
-        if stream {
-            // Process the stream line by line
+        if let Some(sink) = sink {
+            // Process the stream line by line; OpenAI prefixes each event with "data: "
             let reader = std::io::BufReader::new(response);
-            
-            for line in reader.lines() {
-                let line = line?;
-                if line.is_empty() || line == "data: [DONE]" {
-                    continue;
+            let max_tokens = payload["max_tokens"].as_u64().or(payload["max_completion_tokens"].as_u64());
+            let mut sink = Tee::new().with(sink).with(ProgressSink::open(max_tokens));
+            let completion = read_stream("OpenAI", reader, Some("data: "), |event, completion| {
+                // Extract content from the response
+                if let Some(content) = event["choices"][0]["delta"]["content"].as_str() {
+                    sink.write(content)?;
+                    completion.content.push_str(content);
                 }
-                
-                // OpenAI prefixes each line with "data: "
-                if let Some(json_str) = line.strip_prefix("data: ") {
-                    // Parse JSON data
-                    if let Ok(json_response) = serde_json::from_str::<serde_json::Value>(json_str) {
-                        // Extract content from the response
-                        if let Some(content) = json_response["choices"][0]["delta"]["content"].as_str() {
-                            print!("{}", content);
-                            std::io::stdout().flush()?;
-                            full_response.push_str(content);
-                        }
-                    }
+                // The final chunk carries the finish reason
+                if let Some(reason) = event["choices"][0]["finish_reason"].as_str() {
+                    completion.finish_reason = Some(reason.to_string());
                 }
-            }
-            
-            println!("\n"); // Add a newline at the end
-        } else {
-            // Handle non-streaming response
-            let json_response: serde_json::Value = response.json()?;
-            
-            if let Some(content) = json_response["choices"][0]["message"]["content"].as_str() {
-                full_response = content.to_string();
-            }
+                Ok(())
+            })?;
+            return Ok(completion);
         }
         
-        Ok(full_response)
+        // Handle non-streaming response
+        let json_response: serde_json::Value = response.json()?;
+        let mut full_response = String::new();
+        
+        if let Some(content) = json_response["choices"][0]["message"]["content"].as_str() {
+            full_response = content.to_string();
+        }
+        let finish_reason = json_response["choices"][0]["finish_reason"].as_str().map(str::to_string);
+        
+        Ok(Completion { content: full_response, finish_reason })
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.streaming
     }
 }
\ No newline at end of file
@@ -0,0 +1,214 @@
+// Provider/model capability registry
+//
+// Features that depend on what a provider or model supports should query
+// `capabilities()` instead of matching provider strings themselves.
+
+/// Features a provider/model combination supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    /// Accepts image inputs
+    pub vision: bool,
+    /// Has a native JSON output mode
+    pub json_mode: bool,
+    /// Accepts a separate system prompt
+    pub system_prompt: bool,
+    /// Supports streamed responses
+    pub streaming: bool,
+    /// Accepts custom stop sequences
+    pub stop_sequences: bool,
+    /// Accepts a sampling seed for reproducible output
+    pub seed: bool,
+    /// Emits reasoning inline as `<think>` blocks that --no-thinking can strip
+    pub visible_thinking: bool,
+    /// Accepts a reasoning effort level (low/medium/high)
+    pub reasoning_effort: bool,
+    /// Accepts a sampling temperature
+    pub temperature: bool,
+}
+
+// Model-specific overrides, matched by prefix against the model name
+struct ModelRule {
+    prefix: &'static str,
+    vision: bool,
+    json_mode: bool,
+    system_prompt: bool,
+    stop_sequences: bool,
+    reasoning_effort: bool,
+    temperature: bool,
+}
+
+const OPENAI_MODELS: &[ModelRule] = &[
+    ModelRule { prefix: "o1-mini", vision: false, json_mode: false, system_prompt: false, stop_sequences: false, reasoning_effort: false, temperature: false },
+    ModelRule { prefix: "o1", vision: true, json_mode: true, system_prompt: true, stop_sequences: false, reasoning_effort: true, temperature: false },
+    ModelRule { prefix: "o3-mini", vision: false, json_mode: true, system_prompt: true, stop_sequences: false, reasoning_effort: true, temperature: false },
+    ModelRule { prefix: "o3", vision: true, json_mode: true, system_prompt: true, stop_sequences: false, reasoning_effort: true, temperature: false },
+    ModelRule { prefix: "o4-mini", vision: true, json_mode: true, system_prompt: true, stop_sequences: false, reasoning_effort: true, temperature: false },
+    ModelRule { prefix: "gpt-5", vision: true, json_mode: true, system_prompt: true, stop_sequences: false, reasoning_effort: true, temperature: false },
+    ModelRule { prefix: "gpt-4.1", vision: true, json_mode: true, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "gpt-4o", vision: true, json_mode: true, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "gpt-4-turbo", vision: true, json_mode: true, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "gpt-4", vision: false, json_mode: false, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "gpt-3.5-turbo", vision: false, json_mode: true, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+];
+
+const ANTHROPIC_MODELS: &[ModelRule] = &[
+    ModelRule { prefix: "claude-3", vision: true, json_mode: false, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "claude-opus-4", vision: true, json_mode: false, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "claude-sonnet-4", vision: true, json_mode: false, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "claude-haiku-4", vision: true, json_mode: false, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+];
+
+const GEMINI_MODELS: &[ModelRule] = &[
+    ModelRule { prefix: "gemini-1.0-pro-vision", vision: true, json_mode: false, system_prompt: false, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "gemini-1.0", vision: false, json_mode: false, system_prompt: false, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "gemini-pro-vision", vision: true, json_mode: false, system_prompt: false, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "gemini-pro", vision: false, json_mode: false, system_prompt: false, stop_sequences: true, reasoning_effort: false, temperature: true },
+    ModelRule { prefix: "gemini-", vision: true, json_mode: true, system_prompt: true, stop_sequences: true, reasoning_effort: false, temperature: true },
+];
+
+// Ollama model families that accept images
+const OLLAMA_VISION_MODELS: &[&str] = &[
+    "llava", "bakllava", "llama3.2-vision", "llama4", "gemma3", "moondream", "minicpm-v", "qwen2.5vl",
+];
+
+// Known context windows in tokens, matched by prefix against the model name
+const CONTEXT_WINDOWS: &[(&str, &str, u64)] = &[
+    ("openai", "gpt-5", 400_000),
+    ("openai", "gpt-4.1", 1_047_576),
+    ("openai", "gpt-4o", 128_000),
+    ("openai", "gpt-4-turbo", 128_000),
+    ("openai", "gpt-4-32k", 32_768),
+    ("openai", "gpt-4", 8_192),
+    ("openai", "gpt-3.5-turbo", 16_385),
+    ("openai", "o1-mini", 128_000),
+    ("openai", "o1", 200_000),
+    ("openai", "o3", 200_000),
+    ("openai", "o4-mini", 200_000),
+    ("anthropic", "claude-", 200_000),
+    ("gemini", "gemini-1.5-pro", 2_097_152),
+    ("gemini", "gemini-1.0", 32_760),
+    ("gemini", "gemini-pro", 32_760),
+    ("gemini", "gemini-", 1_048_576),
+];
+
+// Name prefixes of the model families each hosted provider serves
+const MODEL_FAMILIES: &[(&str, &str)] = &[
+    ("openai", "gpt-"),
+    ("openai", "chatgpt-"),
+    ("openai", "o1"),
+    ("openai", "o3"),
+    ("openai", "o4"),
+    ("anthropic", "claude-"),
+    ("gemini", "gemini-"),
+];
+
+/// Whether the model name belongs to one of the provider's known model families.
+/// `None` for providers without fixed families, such as Ollama, whose models are
+/// whatever has been pulled locally.
+pub fn is_provider_model(provider: &str, model: &str) -> Option<bool> {
+    let provider = provider.to_lowercase();
+    let model = model.to_lowercase();
+    let mut families = MODEL_FAMILIES.iter().filter(|(known_provider, _)| *known_provider == provider).peekable();
+    families.peek()?;
+    Some(families.any(|(_, prefix)| model.starts_with(prefix)))
+}
+
+/// Sampling parameters for a request; `None` leaves the value to the provider
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Sampling {
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u32>,
+}
+
+// Recommended sampling defaults, matched by prefix against the model name (most specific first)
+const MODEL_SAMPLING: &[(&str, &str, Sampling)] = &[
+    // Flash models are picked for speed, so keep answers short and focused
+    ("gemini", "gemini-1.5-flash", Sampling { temperature: Some(0.4), max_tokens: Some(1024) }),
+    ("gemini", "gemini-2.0-flash", Sampling { temperature: Some(0.4), max_tokens: Some(1024) }),
+    ("gemini", "gemini-2.5-flash", Sampling { temperature: Some(0.4), max_tokens: Some(1024) }),
+    ("gemini", "gemini-", Sampling { temperature: Some(0.7), max_tokens: Some(2048) }),
+    ("anthropic", "claude-", Sampling { temperature: None, max_tokens: Some(2048) }),
+    ("ollama", "", Sampling { temperature: None, max_tokens: Some(2048) }),
+];
+
+/// Recommended temperature and max_tokens for the given provider and model,
+/// used for whatever the settings don't specify
+pub fn default_sampling(provider: &str, model: &str) -> Sampling {
+    let provider = provider.to_lowercase();
+    let model = model.to_lowercase();
+    MODEL_SAMPLING.iter()
+        .find(|(known_provider, prefix, _)| *known_provider == provider && model.starts_with(prefix))
+        .map(|(_, _, sampling)| *sampling)
+        .unwrap_or_default()
+}
+
+/// Sampling to send: requested values win over the model's defaults, and the
+/// temperature is left out for models that don't accept one
+pub fn resolve_sampling(provider: &str, model: &str, requested: Sampling) -> Sampling {
+    let defaults = default_sampling(provider, model);
+    let temperature = requested.temperature.or(defaults.temperature)
+        .filter(|_| capabilities(provider, model).temperature);
+    Sampling { temperature, max_tokens: requested.max_tokens.or(defaults.max_tokens) }
+}
+
+/// Context window of the given provider and model in tokens, if known.
+/// Ollama models are not listed because their window depends on the local `num_ctx`,
+/// which `ApiClient::context_window` takes from the provider's settings instead.
+pub fn context_window(provider: &str, model: &str) -> Option<u64> {
+    let provider = provider.to_lowercase();
+    let model = model.to_lowercase();
+    CONTEXT_WINDOWS.iter()
+        .find(|(known_provider, prefix, _)| *known_provider == provider && model.starts_with(prefix))
+        .map(|(_, _, tokens)| *tokens)
+}
+
+/// Look up what the given provider and model support.
+/// Unknown providers report no capabilities; unknown models of a known provider
+/// get only the features the provider offers for every model.
+pub fn capabilities(provider: &str, model: &str) -> Capabilities {
+    let model = model.to_lowercase();
+
+    match provider.to_lowercase().as_str() {
+        "openai" => {
+            let base = Capabilities { streaming: true, seed: true, system_prompt: true, temperature: true, ..Default::default() };
+            apply_model_rules(base, OPENAI_MODELS, &model)
+        }
+        "anthropic" => {
+            let base = Capabilities { streaming: true, system_prompt: true, stop_sequences: true, temperature: true, ..Default::default() };
+            apply_model_rules(base, ANTHROPIC_MODELS, &model)
+        }
+        "gemini" => {
+            let base = Capabilities { streaming: true, seed: true, temperature: true, ..Default::default() };
+            apply_model_rules(base, GEMINI_MODELS, &model)
+        }
+        "ollama" => Capabilities {
+            vision: OLLAMA_VISION_MODELS.iter().any(|family| model.starts_with(family)),
+            json_mode: true,
+            system_prompt: true,
+            streaming: true,
+            stop_sequences: true,
+            seed: true,
+            // Local reasoning models (deepseek-r1, qwq, qwen3, ...) put their reasoning in <think> tags
+            visible_thinking: true,
+            reasoning_effort: false,
+            temperature: true,
+        },
+        _ => Capabilities::default(),
+    }
+}
+
+// Apply the first matching model rule on top of the provider's baseline
+fn apply_model_rules(base: Capabilities, rules: &[ModelRule], model: &str) -> Capabilities {
+    match rules.iter().find(|rule| model.starts_with(rule.prefix)) {
+        Some(rule) => Capabilities {
+            vision: rule.vision,
+            json_mode: rule.json_mode,
+            system_prompt: rule.system_prompt,
+            stop_sequences: rule.stop_sequences,
+            reasoning_effort: rule.reasoning_effort,
+            temperature: rule.temperature,
+            ..base
+        },
+        None => base,
+    }
+}
@@ -2,7 +2,72 @@ use dialoguer::{theme::ColorfulTheme, Input, Password, Select};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Static description of a provider ola supports
+#[derive(Debug, Clone, Copy)]
+pub struct ProviderInfo {
+    pub name: &'static str,
+    pub requires_api_key: bool,
+    /// Environment variable checked for the API key, if any
+    pub api_key_env: Option<&'static str>,
+    pub default_endpoint: &'static str,
+    pub default_model: &'static str,
+}
+
+/// Every supported provider, in the order they are offered during configuration
+pub const SUPPORTED_PROVIDERS: &[ProviderInfo] = &[
+    ProviderInfo {
+        name: "OpenAI",
+        requires_api_key: true,
+        api_key_env: Some("OPENAI_API_KEY"),
+        default_endpoint: "https://api.openai.com",
+        default_model: "gpt-5",
+    },
+    ProviderInfo {
+        name: "Anthropic",
+        requires_api_key: true,
+        api_key_env: Some("ANTHROPIC_API_KEY"),
+        default_endpoint: "https://api.anthropic.com",
+        default_model: "claude-3-sonnet-20240229",
+    },
+    ProviderInfo {
+        name: "Ollama",
+        requires_api_key: false,
+        api_key_env: None,
+        default_endpoint: "http://localhost:11434",
+        default_model: "llama2",
+    },
+    ProviderInfo {
+        name: "Gemini",
+        requires_api_key: true,
+        api_key_env: Some("GEMINI_API_KEY"),
+        default_endpoint: "https://generativelanguage.googleapis.com",
+        default_model: "gemini-1.5-pro",
+    },
+];
+
+/// Models offered for providers without a model listing endpoint.
+/// Ollama models come from the local instance instead (see `fetch_ollama_models`).
+pub fn builtin_models(provider: &str) -> &'static [&'static str] {
+    match provider {
+        "OpenAI" => &["gpt-5", "gpt-4o", "gpt-4", "o3", "o3-pro", "o4", "o4-mini", "o4-mini-high"],
+        "Anthropic" => &[
+            "claude-3-opus-20240229",
+            "claude-3-sonnet-20240229",
+            "claude-3-haiku-20240307",
+            "claude-2.1",
+            "claude-2.0",
+        ],
+        "Gemini" => &["gemini-1.5-pro", "gemini-1.5-flash", "gemini-1.0-pro", "gemini-1.0-pro-vision"],
+        _ => &[],
+    }
+}
+
+/// Look up a supported provider by name
+pub fn provider_info(name: &str) -> Option<&'static ProviderInfo> {
+    SUPPORTED_PROVIDERS.iter().find(|info| info.name == name)
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProviderConfig {
@@ -35,22 +100,26 @@ impl Config {
         // Depending on file extension, use either JSON or YAML
         let config = if config_path.extension().and_then(|e| e.to_str()) == Some("json") {
             serde_json::from_str(&config_str)
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                .map_err(|e| crate::settings::invalid_json_file(&config_path, &e))?
         } else if config_path.extension().and_then(|e| e.to_str()) == Some("yaml") || 
                   config_path.extension().and_then(|e| e.to_str()) == Some("yml") {
             serde_yaml::from_str(&config_str)
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                .map_err(|e| crate::settings::invalid_yaml_file(&config_path, &config_str, &e))?
         } else {
             // Default to JSON for backward compatibility
             serde_json::from_str(&config_str)
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                .map_err(|e| crate::settings::invalid_json_file(&config_path, &e))?
         };
         
         Ok(config)
     }
 
     pub fn save(&self) -> Result<(), io::Error> {
-        let config_path = get_config_path()?;
+        self.save_to(&get_config_path()?)
+    }
+
+    // Write the configuration to a specific file, in the format its extension names
+    fn save_to(&self, config_path: &Path) -> Result<(), io::Error> {
         let config_dir = config_path.parent().unwrap();
         fs::create_dir_all(config_dir)?;
 
@@ -60,23 +129,23 @@ impl Config {
                 .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
         } else if config_path.extension().and_then(|e| e.to_str()) == Some("yaml") || 
                   config_path.extension().and_then(|e| e.to_str()) == Some("yml") {
-            serde_yaml::to_string(self)
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            // Settings share the YAML file, so their keys are kept
+            crate::settings::merged_yaml(config_path, self)?
         } else {
             // Default to JSON for backward compatibility
             serde_json::to_string_pretty(self)
                 .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
         };
         
-        fs::write(&config_path, config_str)?;
+        fs::write(config_path, config_str)?;
 
         // Set restrictive permissions on config file (600)
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&config_path)?.permissions();
+            let mut perms = fs::metadata(config_path)?.permissions();
             perms.set_mode(0o600);
-            fs::set_permissions(&config_path, perms)?;
+            fs::set_permissions(config_path, perms)?;
         }
 
         Ok(())
@@ -108,6 +177,70 @@ impl Config {
             detect_provider_from_env()
         }
     }
+
+    pub fn get_provider(&self, name: &str) -> Option<ProviderConfig> {
+        self.providers
+            .iter()
+            .find(|p| p.provider.eq_ignore_ascii_case(name))
+            .map(|provider| {
+                let mut config = provider.clone();
+                config.api_key = get_api_key_with_env_fallback(&config.provider, &config.api_key);
+                config
+            })
+    }
+
+    /// Make a configured provider the active one, returning its name as configured
+    pub fn use_provider(&mut self, name: &str) -> Result<String, io::Error> {
+        let provider = self.configured_name(name)?;
+        self.active_provider = provider.clone();
+        Ok(provider)
+    }
+
+    /// Remove a configured provider, returning its name as configured.
+    /// Removing the active provider leaves none active.
+    pub fn remove_provider(&mut self, name: &str) -> Result<String, io::Error> {
+        let provider = self.configured_name(name)?;
+        self.providers.retain(|p| p.provider != provider);
+        if self.active_provider == provider {
+            self.active_provider.clear();
+        }
+        Ok(provider)
+    }
+
+    // Name of the configured provider matching `name` in any case
+    fn configured_name(&self, name: &str) -> Result<String, io::Error> {
+        self.providers
+            .iter()
+            .find(|p| p.provider.eq_ignore_ascii_case(name))
+            .map(|p| p.provider.clone())
+            .ok_or_else(|| {
+                let configured: Vec<&str> = self.providers.iter().map(|p| p.provider.as_str()).collect();
+                let hint = if configured.is_empty() {
+                    "none are configured yet; run 'ola configure' first".to_string()
+                } else {
+                    format!("configured providers: {}", configured.join(", "))
+                };
+                io::Error::new(io::ErrorKind::NotFound, format!("Provider '{}' is not configured ({})", name, hint))
+            })
+    }
+
+    /// Provider/model pairs offered when switching providers for a single run.
+    /// Each configured provider's own model comes first, then its built-in models.
+    pub fn model_choices(&self) -> Vec<(String, String)> {
+        let mut choices = Vec::new();
+        for provider in &self.providers {
+            let configured = provider.model.clone()
+                .or_else(|| provider_info(&provider.provider).map(|info| info.default_model.to_string()));
+            let models = configured.into_iter()
+                .chain(builtin_models(&provider.provider).iter().map(|m| m.to_string()));
+            for model in models {
+                if !choices.iter().any(|(p, m): &(String, String)| p == &provider.provider && m == &model) {
+                    choices.push((provider.provider.clone(), model));
+                }
+            }
+        }
+        choices
+    }
 }
 
 fn get_api_key_with_env_fallback(provider: &str, configured_key: &str) -> String {
@@ -115,30 +248,40 @@ fn get_api_key_with_env_fallback(provider: &str, configured_key: &str) -> String
         return configured_key.to_string();
     }
     
-    let env_var = match provider {
-        "OpenAI" => "OPENAI_API_KEY",
-        "Anthropic" => "ANTHROPIC_API_KEY", 
-        "Gemini" => "GEMINI_API_KEY",
-        _ => return configured_key.to_string(),
+    let env_var = match provider_info(provider).and_then(|info| info.api_key_env) {
+        Some(env_var) => env_var,
+        None => return configured_key.to_string(),
     };
     
     std::env::var(env_var).unwrap_or_else(|_| configured_key.to_string())
 }
 
+/// Pick a provider from API key environment variables, in the `env_provider_priority` order
 pub fn detect_provider_from_env() -> Option<ProviderConfig> {
-    let providers = [
-        ("OpenAI", "OPENAI_API_KEY", "gpt-5"),
-        ("Anthropic", "ANTHROPIC_API_KEY", "claude-3-sonnet-20240229"),
-        ("Gemini", "GEMINI_API_KEY", "gemini-1.5-pro"),
-    ];
+    let settings = crate::settings::Settings::load().unwrap_or_default();
+    detect_provider_from_env_with_priority(&settings.env_provider_priority)
+}
+
+/// Pick a provider from API key environment variables, trying the providers named in
+/// `priority` first and the remaining supported providers afterwards
+pub fn detect_provider_from_env_with_priority(priority: &[String]) -> Option<ProviderConfig> {
+    let prioritized = priority.iter()
+        .filter_map(|name| SUPPORTED_PROVIDERS.iter().find(|info| info.name.eq_ignore_ascii_case(name)));
+    let mut ordered: Vec<&ProviderInfo> = Vec::new();
+    for info in prioritized.chain(SUPPORTED_PROVIDERS) {
+        if !ordered.iter().any(|seen| seen.name == info.name) {
+            ordered.push(info);
+        }
+    }
     
-    for (provider_name, env_var, default_model) in providers {
+    for info in ordered {
+        let Some(env_var) = info.api_key_env else { continue };
         if let Ok(api_key) = std::env::var(env_var) {
             if !api_key.trim().is_empty() {
                 return Some(ProviderConfig {
-                    provider: provider_name.to_string(),
+                    provider: info.name.to_string(),
                     api_key,
-                    model: Some(default_model.to_string()),
+                    model: Some(info.default_model.to_string()),
                     additional_settings: None,
                 });
             }
@@ -148,18 +291,54 @@ pub fn detect_provider_from_env() -> Option<ProviderConfig> {
     None
 }
 
+/// Move a legacy `config.json` into `settings.yaml`, keeping every provider and the active
+/// provider. When `settings.yaml` already lists a provider, its entry wins over the JSON one.
+/// The JSON file is renamed to `config.json.bak`, whose path is returned; `None` means there
+/// was no JSON config to migrate.
+pub fn migrate_json_to_yaml() -> Result<Option<PathBuf>, io::Error> {
+    let ola_dir = crate::profile::ola_dir()?;
+    let json_path = ola_dir.join("config.json");
+    let yaml_path = ola_dir.join("settings.yaml");
+    if !json_path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&json_path)?;
+    let legacy: Config = serde_json::from_str(&json)
+        .map_err(|e| crate::settings::invalid_json_file(&json_path, &e))?;
+    let mut config = if yaml_path.exists() {
+        let yaml = fs::read_to_string(&yaml_path)?;
+        serde_yaml::from_str(&yaml)
+            .map_err(|e| crate::settings::invalid_yaml_file(&yaml_path, &yaml, &e))?
+    } else {
+        Config { active_provider: String::new(), providers: Vec::new() }
+    };
+    for provider in legacy.providers {
+        if !config.providers.iter().any(|p| p.provider.eq_ignore_ascii_case(&provider.provider)) {
+            config.providers.push(provider);
+        }
+    }
+    if config.active_provider.is_empty() {
+        config.active_provider = legacy.active_provider;
+    }
+
+    config.save_to(&yaml_path)?;
+    let backup = PathBuf::from(format!("{}.bak", json_path.display()));
+    fs::rename(&json_path, &backup)?;
+    Ok(Some(backup))
+}
+
 fn get_config_path() -> Result<PathBuf, io::Error> {
-    let home = std::env::var("HOME")
-        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "HOME directory not found"))?;
+    let ola_dir = crate::profile::ola_dir()?;
     
     // Check for settings.yaml first
-    let yaml_path = PathBuf::from(home.clone()).join(".ola").join("settings.yaml");
+    let yaml_path = ola_dir.join("settings.yaml");
     if yaml_path.exists() {
         return Ok(yaml_path);
     }
     
     // Backward compatibility: use config.json if it exists
-    let json_path = PathBuf::from(home).join(".ola").join("config.json");
+    let json_path = ola_dir.join("config.json");
     if json_path.exists() {
         return Ok(json_path);
     }
@@ -238,12 +417,28 @@ fn _run_interactive_config() -> Result<(), io::Error> {
 }
 
 pub fn fetch_ollama_models() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let models = fetch_ollama_installed_models(&ollama_host())?;
+    Ok(models.into_iter().map(|model| model.name).collect())
+}
+
+/// A model installed in Ollama, as reported by `/api/tags`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledModel {
+    pub name: String,
+    /// Size of the model on disk, in bytes
+    pub size: u64,
+    /// When the model was last pulled or changed
+    pub modified_at: Option<String>,
+}
+
+/// List the models installed in Ollama, via its `/api/tags` endpoint
+pub fn fetch_ollama_installed_models(base_url: &str) -> Result<Vec<InstalledModel>, Box<dyn std::error::Error>> {
     let client = reqwest::blocking::Client::builder()
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
     
     let response = client
-        .get("http://localhost:11434/api/tags")
+        .get(format!("{}/api/tags", base_url))
         .send()?;
     
     if !response.status().is_success() {
@@ -251,20 +446,153 @@ pub fn fetch_ollama_models() -> Result<Vec<String>, Box<dyn std::error::Error>>
     }
     
     let models_response: serde_json::Value = response.json()?;
-    let mut model_names = Vec::new();
+    let installed = models_response["models"].as_array()
+        .map(|models| {
+            models.iter()
+                .filter_map(|model| {
+                    Some(InstalledModel {
+                        name: model["name"].as_str()?.to_string(),
+                        size: model["size"].as_u64().unwrap_or(0),
+                        modified_at: model["modified_at"].as_str().map(str::to_string),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
     
-    if let Some(models) = models_response["models"].as_array() {
-        for model in models {
-            if let Some(name) = model["name"].as_str() {
-                model_names.push(name.to_string());
-            }
-        }
+    Ok(installed)
+}
+
+/// A model Ollama currently holds in memory, as reported by `/api/ps`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedModel {
+    pub name: String,
+    /// Memory used by the model, in bytes
+    pub size: u64,
+    /// When Ollama will unload the model if it stays idle
+    pub expires_at: Option<String>,
+}
+
+/// Endpoint of the configured Ollama provider, or the default local one
+pub fn ollama_base_url(config: &Config) -> String {
+    config.get_provider("Ollama")
+        .and_then(|provider| provider.additional_settings)
+        .and_then(|settings| settings.get("base_url").and_then(|url| url.as_str()).map(normalize_base_url))
+        .unwrap_or_else(|| provider_info("Ollama").map(|info| info.default_endpoint).unwrap_or_default().to_string())
+}
+
+/// Ollama server to list models from: `OLLAMA_HOST` when set, then the configured Ollama
+/// base_url, then the default endpoint
+pub fn ollama_host() -> String {
+    match std::env::var("OLLAMA_HOST") {
+        Ok(host) if !host.trim().is_empty() => normalize_base_url(&host),
+        _ => Config::load()
+            .map(|config| ollama_base_url(&config))
+            .unwrap_or_else(|_| provider_info("Ollama").map(|info| info.default_endpoint).unwrap_or_default().to_string()),
+    }
+}
+
+/// List the models Ollama currently has loaded, via its `/api/ps` endpoint
+pub fn fetch_ollama_loaded_models(base_url: &str) -> Result<Vec<LoadedModel>, Box<dyn std::error::Error>> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()?;
+    
+    let response = client
+        .get(format!("{}/api/ps", base_url))
+        .send()?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Ollama API error: {}", response.status()).into());
     }
     
-    Ok(model_names)
+    let ps_response: serde_json::Value = response.json()?;
+    let loaded = ps_response["models"].as_array()
+        .map(|models| {
+            models.iter()
+                .filter_map(|model| {
+                    Some(LoadedModel {
+                        name: model["name"].as_str()?.to_string(),
+                        size: model["size"].as_u64().unwrap_or(0),
+                        expires_at: model["expires_at"].as_str().map(str::to_string),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    
+    Ok(loaded)
+}
+
+/// Normalize a configured base_url before endpoint paths are appended:
+/// trims whitespace and trailing slashes, and adds a scheme when missing
+/// (`http://` for local hosts, `https://` otherwise).
+pub fn normalize_base_url(url: &str) -> String {
+    let trimmed = url.trim();
+    if let Some((scheme, rest)) = trimmed.split_once("://") {
+        return format!("{}://{}", scheme, rest.trim_end_matches('/'));
+    }
+    let trimmed = trimmed.trim_end_matches('/');
+
+    let host = trimmed.split(['/', ':']).next().unwrap_or("");
+    let is_local = matches!(host, "localhost" | "127.0.0.1" | "0.0.0.0") || trimmed.starts_with("[::1]");
+    let scheme = if is_local {
+        "http"
+    } else {
+        "https"
+    };
+    format!("{}://{}", scheme, trimmed)
+}
+
+/// Check that a base_url is usable after normalization
+pub fn validate_base_url(url: &str) -> Result<(), String> {
+    let normalized = normalize_base_url(url);
+    let (scheme, rest) = normalized
+        .split_once("://")
+        .ok_or_else(|| format!("Invalid base_url '{}'", url))?;
+
+    if scheme != "http" && scheme != "https" {
+        return Err(format!("base_url '{}' must use http or https", url));
+    }
+
+    let host = rest.split('/').next().unwrap_or("");
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err(format!("base_url '{}' has no valid host", url));
+    }
+
+    Ok(())
+}
+
+/// Check that a sampling temperature is within the 0.0-2.0 range providers accept
+pub fn check_temperature(temperature: f64) -> Result<(), String> {
+    if (0.0..=2.0).contains(&temperature) {
+        Ok(())
+    } else {
+        Err(format!("temperature must be between 0.0 and 2.0, got {}", temperature))
+    }
 }
 
 pub fn validate_provider_config(config: &ProviderConfig) -> Result<(), String> {
+    // Validate a custom endpoint if one is configured
+    if let Some(base_url) = config.additional_settings.as_ref()
+        .and_then(|settings| settings.get("base_url"))
+        .and_then(|url| url.as_str())
+    {
+        validate_base_url(base_url)?;
+    }
+
+    // Sampling overrides must be usable by every provider
+    let sampling = config.additional_settings.as_ref();
+    if let Some(temperature) = sampling.and_then(|settings| settings.get("temperature")) {
+        let temperature = temperature.as_f64().ok_or("temperature must be a number")?;
+        check_temperature(temperature)?;
+    }
+    if let Some(max_tokens) = sampling.and_then(|settings| settings.get("max_tokens")) {
+        if max_tokens.as_u64().unwrap_or(0) == 0 {
+            return Err("max_tokens must be a positive whole number".to_string());
+        }
+    }
+
     // Provider-specific validation
     match config.provider.as_str() {
         "OpenAI" => {
@@ -328,6 +656,111 @@ pub fn validate_provider_config(config: &ProviderConfig) -> Result<(), String> {
     Ok(())
 }
 
+/// One provider in a `configure --from-file` file. The API key can be given inline or,
+/// to keep secrets out of the file, as the name of an environment variable holding it.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ProviderEntry {
+    provider: String,
+    api_key: Option<String>,
+    api_key_env: Option<String>,
+    model: Option<String>,
+    base_url: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ProviderFile {
+    active_provider: Option<String>,
+    providers: Vec<ProviderEntry>,
+}
+
+impl ProviderEntry {
+    fn into_config(self) -> Result<ProviderConfig, String> {
+        let info = SUPPORTED_PROVIDERS.iter()
+            .find(|info| info.name.eq_ignore_ascii_case(self.provider.trim()))
+            .ok_or_else(|| format!("Unsupported provider: {}", self.provider))?;
+        let api_key = match (self.api_key, self.api_key_env) {
+            (Some(_), Some(_)) => return Err(format!("{}: set either api_key or api_key_env, not both", info.name)),
+            (Some(key), None) => key,
+            (None, Some(env_var)) => std::env::var(&env_var)
+                .ok()
+                .filter(|key| !key.trim().is_empty())
+                .ok_or_else(|| format!("{}: environment variable {} is not set", info.name, env_var))?,
+            // Fall back to the provider's usual environment variable, if any
+            (None, None) => info.api_key_env.and_then(|env_var| std::env::var(env_var).ok()).unwrap_or_default(),
+        };
+        Ok(ProviderConfig {
+            provider: info.name.to_string(),
+            api_key,
+            model: Some(self.model.unwrap_or_else(|| info.default_model.to_string())),
+            additional_settings: self.base_url.map(|url| serde_json::json!({ "base_url": url })),
+        })
+    }
+}
+
+/// Read the providers described by a YAML or JSON file (`ola configure --from-file`) and
+/// validate each with `validate_provider_config`. Returns them with the name of the provider
+/// to make active: the file's `active_provider`, or else the first one listed.
+pub fn read_provider_file(path: &Path) -> Result<(Vec<ProviderConfig>, String), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    // YAML is a superset of JSON, so one parser covers both
+    let file: ProviderFile = serde_yaml::from_str(&content).map_err(|e| format!("Invalid provider file: {}", e))?;
+    if file.providers.is_empty() {
+        return Err("The file lists no providers".to_string());
+    }
+
+    let mut providers = Vec::new();
+    for entry in file.providers {
+        let provider = entry.into_config()?;
+        validate_provider_config(&provider).map_err(|e| format!("{}: {}", provider.provider, e))?;
+        providers.push(provider);
+    }
+
+    let active = match file.active_provider {
+        Some(name) => providers.iter()
+            .find(|p| p.provider.eq_ignore_ascii_case(name.trim()))
+            .map(|p| p.provider.clone())
+            .ok_or_else(|| format!("active_provider '{}' is not one of the listed providers", name))?,
+        None => providers[0].provider.clone(),
+    };
+    Ok((providers, active))
+}
+
+/// Check that a provider endpoint is reachable and accepts the configured credentials
+/// by calling a lightweight read-only endpoint (version or model listing).
+pub fn check_connection(config: &ProviderConfig) -> Result<(), String> {
+    let info = provider_info(&config.provider)
+        .ok_or_else(|| format!("Unsupported provider: {}", config.provider))?;
+    let base_url = config.additional_settings.as_ref()
+        .and_then(|settings| settings.get("base_url"))
+        .and_then(|url| url.as_str())
+        .map(normalize_base_url)
+        .unwrap_or_else(|| info.default_endpoint.to_string());
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let request = match info.name {
+        "OpenAI" => client.get(format!("{}/v1/models", base_url))
+            .header("Authorization", format!("Bearer {}", config.api_key)),
+        "Anthropic" => client.get(format!("{}/v1/models", base_url))
+            .header("x-api-key", &config.api_key)
+            .header("anthropic-version", "2023-06-01"),
+        "Gemini" => client.get(format!("{}/v1beta/models?key={}", base_url, config.api_key)),
+        _ => client.get(format!("{}/api/version", base_url)),
+    };
+
+    let response = request.send().map_err(|e| format!("Could not reach {}: {}", base_url, e))?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("{} responded with {}", base_url, response.status()))
+    }
+}
+
 // Module-level functions for use in main.rs
 pub fn add_provider(provider: ProviderConfig) {
     match Config::load() {
@@ -7,6 +7,9 @@ pub mod prompt;
 pub mod settings;
 pub mod models;
 pub mod project;
+pub mod capabilities;
+pub mod profile;
+pub mod logs;
 
 // API communication layer
 pub mod api;
@@ -0,0 +1,176 @@
+// Reading and summarizing the session log
+//
+// Each prompt run appends one JSON object per line to `behavior.log_file`
+// (see prompt::log_session). Lines that can't be read are skipped so one bad
+// entry doesn't hide the rest of the log.
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The parts of a logged session used for summaries
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// Session id; entries logged before ids were added have none
+    pub id: Option<String>,
+    pub timestamp: DateTime<Local>,
+    /// Provider name, for entries that recorded one
+    pub provider: Option<String>,
+    pub model: String,
+    /// The run's goals; entries from prompts without goals leave this out
+    pub goals: Option<String>,
+    /// Requested return format, for entries that recorded one
+    pub return_format: Option<String>,
+    /// The run's warnings, for entries that recorded them
+    pub warnings: Option<String>,
+    pub output_length: u64,
+    /// Token count, for entries that recorded one
+    pub tokens: Option<u64>,
+    /// Cost in USD, for entries that recorded one
+    pub cost: Option<f64>,
+    /// Labels given with `--tag`
+    pub tags: Vec<String>,
+}
+
+impl LogEntry {
+    /// Read an entry from a log line's JSON; entries without a valid timestamp are rejected
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let timestamp = DateTime::parse_from_rfc3339(value["timestamp"].as_str()?).ok()?;
+        Some(Self {
+            id: value["id"].as_str().map(str::to_string),
+            timestamp: timestamp.with_timezone(&Local),
+            provider: value["provider"].as_str().map(str::to_string),
+            model: value["model"].as_str().unwrap_or("unknown").to_string(),
+            goals: value["goals"].as_str().map(str::to_string),
+            return_format: value["return_format"].as_str().map(str::to_string),
+            warnings: value["warnings"].as_str().map(str::to_string),
+            output_length: value["output_length"].as_u64().unwrap_or(0),
+            tokens: value["tokens"].as_u64(),
+            cost: value["cost"].as_f64(),
+            tags: value["tags"]
+                .as_array()
+                .map(|tags| tags.iter().filter_map(|tag| tag.as_str().map(str::to_string)).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Whether the entry was tagged with every one of `tags`
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|tag| self.tags.contains(tag))
+    }
+}
+
+/// Sessions logged on one day
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaySummary {
+    pub date: NaiveDate,
+    pub sessions: usize,
+    /// Sessions per model
+    pub models: BTreeMap<String, usize>,
+    pub output_length: u64,
+    /// Total tokens over the entries that recorded them; `None` if none did
+    pub tokens: Option<u64>,
+    /// Total cost over the entries that recorded it; `None` if none did
+    pub cost: Option<f64>,
+}
+
+/// Read every entry from a log file; a missing file is an empty log
+pub fn read_entries(path: &Path) -> io::Result<Vec<LogEntry>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|value| LogEntry::from_json(&value))
+        .collect())
+}
+
+/// The entry with the given session id, or the most recent one when `id` is `None`
+pub fn find_session<'a>(entries: &'a [LogEntry], id: Option<&str>) -> Option<&'a LogEntry> {
+    match id {
+        Some(id) => entries.iter().find(|entry| entry.id.as_deref() == Some(id)),
+        None => entries.last(),
+    }
+}
+
+/// Group entries by local date, oldest first, leaving out days before `since`
+pub fn summarize_by_day(entries: &[LogEntry], since: Option<NaiveDate>) -> Vec<DaySummary> {
+    let mut days: BTreeMap<NaiveDate, DaySummary> = BTreeMap::new();
+    for entry in entries {
+        let date = entry.timestamp.date_naive();
+        if since.is_some_and(|since| date < since) {
+            continue;
+        }
+        let day = days.entry(date).or_insert_with(|| DaySummary {
+            date,
+            sessions: 0,
+            models: BTreeMap::new(),
+            output_length: 0,
+            tokens: None,
+            cost: None,
+        });
+        day.sessions += 1;
+        *day.models.entry(entry.model.clone()).or_insert(0) += 1;
+        day.output_length += entry.output_length;
+        if let Some(tokens) = entry.tokens {
+            day.tokens = Some(day.tokens.unwrap_or(0) + tokens);
+        }
+        if let Some(cost) = entry.cost {
+            day.cost = Some(day.cost.unwrap_or(0.0) + cost);
+        }
+    }
+    days.into_values().collect()
+}
+
+/// Write entries as CSV with a header row, leaving out entries before `since`; fields
+/// an entry didn't record are left empty. Returns the number of rows written
+pub fn write_csv<W: Write>(out: &mut W, entries: &[LogEntry], since: Option<NaiveDate>) -> io::Result<usize> {
+    let mut rows = 0;
+    writeln!(out, "timestamp,provider,model,goals,output_length,tokens,cost")?;
+    for entry in entries {
+        if since.is_some_and(|since| entry.timestamp.date_naive() < since) {
+            continue;
+        }
+        let fields = [
+            entry.timestamp.to_rfc3339(),
+            entry.provider.clone().unwrap_or_default(),
+            entry.model.clone(),
+            entry.goals.clone().unwrap_or_default(),
+            entry.output_length.to_string(),
+            entry.tokens.map(|tokens| tokens.to_string()).unwrap_or_default(),
+            entry.cost.map(|cost| cost.to_string()).unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(out, "{}", row.join(","))?;
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+/// Quote a CSV field when it holds a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Parse a `--since` value: a date (`2024-05-01`) or a number of days back (`7d`, where
+/// `0d` is today)
+pub fn parse_since(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let value = value.trim();
+    if let Some(days) = value.strip_suffix('d') {
+        let days: i64 = days
+            .parse()
+            .map_err(|_| format!("Invalid --since '{}': use a date like 2024-05-01 or a day count like 7d", value))?;
+        return Ok(today - Duration::days(days));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid --since '{}': use a date like 2024-05-01 or a day count like 7d", value))
+}
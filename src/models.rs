@@ -9,6 +9,9 @@ pub struct ProjectFile {
     pub size: u64,
     pub mime_type: Option<String>,
     pub uploaded_at: DateTime<Utc>,
+    /// Stored gzip-compressed on disk; `size` is still the uncompressed size
+    #[serde(default)]
+    pub compressed: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -31,6 +34,9 @@ pub struct Project {
     pub name: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// When the project was last used by `project run`; `None` if it never was
+    #[serde(default)]
+    pub last_accessed_at: Option<DateTime<Utc>>,
     pub files: Vec<ProjectFile>,
     pub goals: Vec<Goal>,
     pub contexts: Vec<Context>,
@@ -44,6 +50,7 @@ impl Project {
             name,
             created_at: now,
             updated_at: now,
+            last_accessed_at: None,
             files: Vec::new(),
             goals: Vec::new(),
             contexts: Vec::new(),
@@ -117,6 +124,58 @@ impl Project {
         self.contexts.sort_by_key(|c| c.order);
         self.updated_at = Utc::now();
     }
+
+    /// Append one goal per item, continuing after the existing goal orders
+    pub fn import_goals(&mut self, items: &[String]) -> usize {
+        for item in items {
+            let order = self.goals.len() as u32;
+            self.goals.push(Goal::new(item.clone(), order));
+        }
+        self.updated_at = Utc::now();
+        items.len()
+    }
+
+    /// Append one context per item, continuing after the existing context orders
+    pub fn import_contexts(&mut self, items: &[String]) -> usize {
+        for item in items {
+            let order = self.contexts.len() as u32;
+            self.contexts.push(Context::new(item.clone(), order));
+        }
+        self.updated_at = Utc::now();
+        items.len()
+    }
+}
+
+/// Split a text or markdown file into import entries.
+/// Each non-empty line becomes an entry; markdown list markers (`-`, `*`, `+`, `1.`, `1)`)
+/// are stripped, and blank lines and `#` comments or headings are skipped.
+pub fn parse_import_items(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(strip_list_marker)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Remove a leading bullet or numbered-list marker from a trimmed line
+fn strip_list_marker(line: &str) -> &str {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return rest.trim();
+        }
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        if let Some(item) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return item.trim();
+        }
+    }
+
+    line
 }
 
 impl Goal {
@@ -147,6 +206,7 @@ impl ProjectFile {
             size,
             mime_type,
             uploaded_at: Utc::now(),
+            compressed: false,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,132 @@
+// Profiles: separate sets of settings, providers and projects
+//
+// Everything ola stores lives in one directory. By default that is `~/.ola`
+// (or `$OLA_HOME`); a profile keeps its own copy under `profiles/<name>/`.
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Environment variable naming the profile for this invocation; `--profile` sets it
+/// so recursion waves launched as subprocesses use the same profile
+pub const PROFILE_ENV: &str = "OLA_PROFILE";
+
+/// Name that refers to the top-level directory rather than a profile
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Root directory: `$OLA_HOME` if set, otherwise `~/.ola`
+pub fn base_dir() -> io::Result<PathBuf> {
+    if let Some(dir) = env::var_os("OLA_HOME").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let home = env::var("HOME")
+        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "HOME directory not found"))?;
+    Ok(PathBuf::from(home).join(".ola"))
+}
+
+/// Directory for the profile in use: the one named by `--profile`, otherwise the one
+/// chosen with `ola profile use`, otherwise the root directory
+pub fn ola_dir() -> io::Result<PathBuf> {
+    match current_profile()? {
+        Some(name) => profile_dir(&name),
+        None => base_dir(),
+    }
+}
+
+/// Name of the profile in use, or `None` for the default
+pub fn current_profile() -> io::Result<Option<String>> {
+    if let Ok(name) = env::var(PROFILE_ENV) {
+        return Ok(Some(name).filter(|name| !name.is_empty() && name != DEFAULT_PROFILE));
+    }
+    match fs::read_to_string(base_dir()?.join("active_profile")) {
+        Ok(name) => Ok(Some(name.trim().to_string()).filter(|name| !name.is_empty())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Names of all created profiles, sorted
+pub fn list_profiles() -> io::Result<Vec<String>> {
+    let dir = base_dir()?.join("profiles");
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Create an empty profile and return its directory
+pub fn create_profile(name: &str) -> io::Result<PathBuf> {
+    validate_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "'default' is reserved for the top-level settings"));
+    }
+    let dir = base_dir()?.join("profiles").join(name);
+    if dir.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Profile '{}' already exists", name)));
+    }
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Make `name` the profile used when `--profile` is not given; "default" switches back
+pub fn use_profile(name: &str) -> io::Result<()> {
+    let active_file = base_dir()?.join("active_profile");
+    if name == DEFAULT_PROFILE {
+        return match fs::remove_file(&active_file) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    profile_dir(name)?;
+    fs::write(active_file, name)
+}
+
+/// Delete a profile and everything stored in it. Returns whether it was the profile
+/// chosen with `ola profile use`, in which case the default is used again
+pub fn delete_profile(name: &str) -> io::Result<bool> {
+    if name == DEFAULT_PROFILE {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "'default' is the top-level settings and can't be deleted"));
+    }
+    fs::remove_dir_all(profile_dir(name)?)?;
+    let active_file = base_dir()?.join("active_profile");
+    let was_active = fs::read_to_string(&active_file).is_ok_and(|active| active.trim() == name);
+    if was_active {
+        fs::remove_file(active_file)?;
+    }
+    Ok(was_active)
+}
+
+/// Directory of an existing profile
+pub fn profile_dir(name: &str) -> io::Result<PathBuf> {
+    validate_name(name)?;
+    let dir = base_dir()?.join("profiles").join(name);
+    if !dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Profile '{}' not found; create it with 'ola profile create {}'", name, name),
+        ));
+    }
+    Ok(dir)
+}
+
+// Profile names become directory names, so keep them to a safe character set
+fn validate_name(name: &str) -> io::Result<()> {
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid profile name '{}': use letters, digits, '-' and '_'", name),
+        ))
+    }
+}
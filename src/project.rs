@@ -1,26 +1,111 @@
-use crate::models::{Project, ProjectFile};
+use crate::models::{Context, Goal, Project, ProjectFile};
+use crate::settings::EvictionPolicy;
 use anyhow::{Result, Context as AnyhowContext};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::cell::Cell;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::PathBuf;
 
+// Longest explicit project id accepted by `create_project`
+const MAX_PROJECT_ID_LEN: usize = 64;
+
 pub struct ProjectManager {
     base_path: PathBuf,
+    compress_files: bool,
+    keep_crlf: bool,
+    writes: Cell<usize>,
+}
+
+/// Edits to one project that are written to `project.json` once, on `commit`.
+/// Dropping the transaction without committing discards the edits.
+pub struct ProjectTransaction<'a> {
+    manager: &'a ProjectManager,
+    project: Project,
+}
+
+impl ProjectTransaction<'_> {
+    /// The project being edited
+    pub fn project_mut(&mut self) -> &mut Project {
+        &mut self.project
+    }
+
+    /// Write all edits in a single save and return the updated project
+    pub fn commit(self) -> Result<Project> {
+        self.manager.save_project(&self.project)?;
+        Ok(self.project)
+    }
+}
+
+/// Order of the project list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProjectSort {
+    /// Most recently changed first
+    #[default]
+    Updated,
+    /// Most recently run first; projects never run follow, by last change
+    Accessed,
+    /// By name, ignoring case
+    Name,
+    /// Newest first
+    Created,
+}
+
+/// Sort projects in place by the given order
+pub fn sort_projects(projects: &mut [Project], sort: ProjectSort) {
+    match sort {
+        ProjectSort::Updated => projects.sort_by_key(|project| std::cmp::Reverse(project.updated_at)),
+        ProjectSort::Accessed => projects.sort_by(|a, b| {
+            b.last_accessed_at.cmp(&a.last_accessed_at).then(b.updated_at.cmp(&a.updated_at))
+        }),
+        ProjectSort::Name => projects.sort_by_key(|project| project.name.to_lowercase()),
+        ProjectSort::Created => projects.sort_by_key(|project| std::cmp::Reverse(project.created_at)),
+    }
+}
+
+/// Number of items copied into the target by `ProjectManager::merge_projects`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub goals: usize,
+    pub contexts: usize,
+    pub files: usize,
 }
 
 impl ProjectManager {
     pub fn new() -> Result<Self> {
-        let home = std::env::var("HOME")
-            .map_err(|_| anyhow::anyhow!("HOME directory not found"))?;
-        
-        let base_path = PathBuf::from(home).join(".ola").join("data").join("projects");
+        let base_path = crate::profile::ola_dir()?.join("data").join("projects");
         fs::create_dir_all(&base_path)
             .with_context(|| format!("Failed to create project directory: {}", base_path.display()))?;
         
-        Ok(Self { base_path })
+        Ok(Self { base_path, compress_files: false, keep_crlf: false, writes: Cell::new(0) })
+    }
+
+    /// Store files uploaded through this manager gzip-compressed.
+    /// Existing files are read either way.
+    pub fn with_compression(mut self, compress_files: bool) -> Self {
+        self.compress_files = compress_files;
+        self
     }
 
-    pub fn create_project(&self, name: String) -> Result<Project> {
-        let project = Project::new(name);
+    /// Keep CRLF line endings in text read through this manager instead of converting them to LF
+    pub fn with_keep_crlf(mut self, keep_crlf: bool) -> Self {
+        self.keep_crlf = keep_crlf;
+        self
+    }
+
+    /// Create a project, using `id` instead of a random UUID when given.
+    /// Explicit ids must be filesystem-safe, not reserved, and not already in use.
+    pub fn create_project(&self, name: String, id: Option<&str>) -> Result<Project> {
+        let mut project = Project::new(name);
+        if let Some(id) = id {
+            validate_project_id(id)?;
+            if self.base_path.join(id).exists() {
+                return Err(anyhow::anyhow!("A project with ID '{}' already exists", id));
+            }
+            project.id = id.to_string();
+        }
+
         let project_dir = self.base_path.join(&project.id);
         fs::create_dir_all(&project_dir)
             .with_context(|| format!("Failed to create project directory: {}", project_dir.display()))?;
@@ -50,6 +135,11 @@ impl ProjectManager {
         Ok(Some(project))
     }
 
+    /// Start batching edits to a loaded project; nothing is written until `commit`
+    pub fn begin(&self, project: Project) -> ProjectTransaction<'_> {
+        ProjectTransaction { manager: self, project }
+    }
+
     pub fn save_project(&self, project: &Project) -> Result<()> {
         let project_dir = self.base_path.join(&project.id);
         let project_file = project_dir.join("project.json");
@@ -59,10 +149,18 @@ impl ProjectManager {
         
         fs::write(&project_file, content)
             .with_context(|| format!("Failed to write project file: {}", project_file.display()))?;
+        self.writes.set(self.writes.get() + 1);
         
         Ok(())
     }
 
+    /// Number of times this manager has written a `project.json`
+    // Kept for library users checking that edits were batched; the binary doesn't ask
+    #[allow(dead_code)]
+    pub fn writes(&self) -> usize {
+        self.writes.get()
+    }
+
     pub fn list_projects(&self) -> Result<Vec<Project>> {
         let mut projects = Vec::new();
         
@@ -81,10 +179,16 @@ impl ProjectManager {
         }
         
         // Sort by updated_at descending (most recent first)
-        projects.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        sort_projects(&mut projects, ProjectSort::Updated);
         Ok(projects)
     }
 
+    /// Record that a project is being used now, without changing `updated_at`
+    pub fn mark_accessed(&self, project: &mut Project) -> Result<()> {
+        project.last_accessed_at = Some(chrono::Utc::now());
+        self.save_project(project)
+    }
+
     pub fn get_default_project(&self) -> Result<Project> {
         // Try to load existing default project
         if let Ok(Some(project)) = self.load_project("default") {
@@ -112,34 +216,50 @@ impl ProjectManager {
         fs::create_dir_all(&files_dir)
             .with_context(|| format!("Failed to create files directory: {}", files_dir.display()))?;
         
-        let file_obj = ProjectFile::new(filename.clone(), content.len() as u64, Self::guess_mime_type(&filename));
-        let file_path = files_dir.join(&file_obj.id);
+        let mut file_obj = ProjectFile::new(filename.clone(), content.len() as u64, Self::guess_mime_type(&filename));
         
-        fs::write(&file_path, content)
-            .with_context(|| format!("Failed to write file: {}", file_path.display()))?;
+        if self.compress_files {
+            let file_path = files_dir.join(format!("{}.gz", file_obj.id));
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(content)
+                .and_then(|_| encoder.finish())
+                .and_then(|compressed| fs::write(&file_path, compressed))
+                .with_context(|| format!("Failed to write file: {}", file_path.display()))?;
+            file_obj.compressed = true;
+        } else {
+            let file_path = files_dir.join(&file_obj.id);
+            fs::write(&file_path, content)
+                .with_context(|| format!("Failed to write file: {}", file_path.display()))?;
+        }
         
         Ok(file_obj)
     }
 
+    /// Read a stored file, decompressing it if it was uploaded compressed
     pub fn download_file(&self, project_id: &str, file_id: &str) -> Result<Option<Vec<u8>>> {
-        let file_path = self.base_path.join(project_id).join("files").join(file_id);
-        
-        if !file_path.exists() {
+        let Some((file_path, compressed)) = self.stored_file_path(project_id, file_id) else {
             return Ok(None);
-        }
+        };
         
         let content = fs::read(&file_path)
             .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
         
-        Ok(Some(content))
+        if !compressed {
+            return Ok(Some(content));
+        }
+        
+        let mut decompressed = Vec::new();
+        GzDecoder::new(content.as_slice())
+            .read_to_end(&mut decompressed)
+            .with_context(|| format!("Failed to decompress file: {}", file_path.display()))?;
+        
+        Ok(Some(decompressed))
     }
 
     pub fn delete_file(&self, project_id: &str, file_id: &str) -> Result<bool> {
-        let file_path = self.base_path.join(project_id).join("files").join(file_id);
-        
-        if !file_path.exists() {
+        let Some((file_path, _)) = self.stored_file_path(project_id, file_id) else {
             return Ok(false);
-        }
+        };
         
         fs::remove_file(&file_path)
             .with_context(|| format!("Failed to delete file: {}", file_path.display()))?;
@@ -151,12 +271,12 @@ impl ProjectManager {
         if let Some(content) = self.download_file(project_id, file_id)? {
             // Try to convert to UTF-8 string
             match String::from_utf8(content.clone()) {
-                Ok(text) => Ok(Some(text)),
+                Ok(text) => Ok(Some(normalize_text(&text, self.keep_crlf))),
                 Err(_) => {
                     // If not valid UTF-8, return base64 encoded content
                     use base64::{Engine, engine::general_purpose};
                     let encoded = general_purpose::STANDARD.encode(&content);
-                    Ok(Some(format!("[Binary file - base64 encoded: {}]", encoded)))
+                    Ok(Some(format!("{}{}]", BINARY_TEXT_PREFIX, encoded)))
                 }
             }
         } else {
@@ -177,6 +297,29 @@ impl ProjectManager {
         Ok(())
     }
     
+    /// Delete every project under the data directory and clear the active project pointer.
+    /// When `keep_default` is set, the default project is preserved. Returns the number of
+    /// projects removed.
+    pub fn purge_projects(&self, keep_default: bool) -> Result<usize> {
+        let mut removed = 0;
+
+        for project in self.list_projects()? {
+            if keep_default && project.id == "default" {
+                continue;
+            }
+            self.delete_project(&project.id)?;
+            removed += 1;
+        }
+
+        // Keep the pointer only if it still refers to the surviving default project
+        match self.get_active_project()? {
+            Some(active_id) if keep_default && active_id == "default" => {}
+            _ => self.clear_active_project()?,
+        }
+
+        Ok(removed)
+    }
+
     pub fn set_active_project(&self, project_id: &str) -> Result<()> {
         // Verify project exists
         if self.load_project(project_id)?.is_none() {
@@ -217,6 +360,19 @@ impl ProjectManager {
         }
     }
     
+    pub fn clear_active_project(&self) -> Result<()> {
+        let active_file = self.base_path.parent()
+            .ok_or_else(|| anyhow::anyhow!("Invalid base path"))?
+            .join("active_project");
+        
+        if active_file.exists() {
+            fs::remove_file(&active_file)
+                .with_context(|| format!("Failed to remove active project file: {}", active_file.display()))?;
+        }
+        
+        Ok(())
+    }
+    
     pub fn edit_project(&self, project_id: &str, new_name: Option<String>) -> Result<Project> {
         let mut project = self.load_project(project_id)?
             .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project_id))?;
@@ -230,6 +386,67 @@ impl ProjectManager {
         Ok(project)
     }
 
+    /// Append the source project's goals, contexts, and files to the target project.
+    /// Copies get fresh ids (file bytes are copied under the new ids) and continue the
+    /// target's ordering. With `dedup`, goals and contexts whose text already exists in
+    /// the target are skipped. The source project is left untouched.
+    pub fn merge_projects(&self, source_id: &str, target_id: &str, dedup: bool) -> Result<MergeSummary> {
+        if source_id == target_id {
+            return Err(anyhow::anyhow!("Cannot merge a project into itself"));
+        }
+
+        let source = self.load_project(source_id)?
+            .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", source_id))?;
+        let mut target = self.load_project(target_id)?
+            .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", target_id))?;
+        let mut summary = MergeSummary::default();
+
+        let mut goals = source.goals.clone();
+        goals.sort_by_key(|g| g.order);
+        for goal in goals {
+            if dedup && target.goals.iter().any(|g| g.text == goal.text) {
+                continue;
+            }
+            let order = target.goals.len() as u32;
+            target.goals.push(Goal::new(goal.text, order));
+            summary.goals += 1;
+        }
+
+        let mut contexts = source.contexts.clone();
+        contexts.sort_by_key(|c| c.order);
+        for context in contexts {
+            if dedup && target.contexts.iter().any(|c| c.text == context.text) {
+                continue;
+            }
+            let order = target.contexts.len() as u32;
+            target.contexts.push(Context::new(context.text, order));
+            summary.contexts += 1;
+        }
+
+        for file in &source.files {
+            let content = self.download_file(source_id, &file.id)?
+                .ok_or_else(|| anyhow::anyhow!("File '{}' is missing from project '{}'", file.filename, source.name))?;
+            let copied = self.upload_file(target_id, file.filename.clone(), &content)?;
+            target.files.push(copied);
+            summary.files += 1;
+        }
+
+        target.updated_at = chrono::Utc::now();
+        self.save_project(&target)?;
+        Ok(summary)
+    }
+
+    // Locate a stored file and whether it is the gzip-compressed variant
+    fn stored_file_path(&self, project_id: &str, file_id: &str) -> Option<(PathBuf, bool)> {
+        let files_dir = self.base_path.join(project_id).join("files");
+        let compressed_path = files_dir.join(format!("{}.gz", file_id));
+        if compressed_path.exists() {
+            return Some((compressed_path, true));
+        }
+        let plain_path = files_dir.join(file_id);
+        plain_path.exists().then_some((plain_path, false))
+    }
+
     fn guess_mime_type(filename: &str) -> Option<String> {
         let extension = std::path::Path::new(filename)
             .extension()
@@ -251,4 +468,69 @@ impl ProjectManager {
             _ => Some("application/octet-stream".to_string()),
         }
     }
-}
\ No newline at end of file
+}
+
+// Start of the text `read_file_as_text` returns for files that aren't UTF-8
+const BINARY_TEXT_PREFIX: &str = "[Binary file - base64 encoded: ";
+
+/// Drop a leading UTF-8 byte order mark and, unless `keep_crlf` is set, turn CRLF line
+/// endings into LF
+pub fn normalize_text(text: &str, keep_crlf: bool) -> String {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    if keep_crlf {
+        text.to_string()
+    } else {
+        text.replace("\r\n", "\n")
+    }
+}
+
+/// Whether text from `read_file_as_text` stands for a binary file
+pub fn is_binary_text(text: &str) -> bool {
+    text.starts_with(BINARY_TEXT_PREFIX) || text.contains('\0')
+}
+
+/// Check that an explicit project id is safe to use as a directory name.
+/// Ids may contain ASCII letters, digits, `-` and `_`, must start with a letter or digit,
+/// and may not be `default` or UUID-shaped (both are reserved for generated projects).
+pub fn validate_project_id(id: &str) -> Result<()> {
+    if id.is_empty() || id.len() > MAX_PROJECT_ID_LEN {
+        return Err(anyhow::anyhow!("Project ID must be between 1 and {} characters", MAX_PROJECT_ID_LEN));
+    }
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow::anyhow!("Project ID '{}' may only contain letters, digits, '-' and '_'", id));
+    }
+    if !id.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return Err(anyhow::anyhow!("Project ID '{}' must start with a letter or digit", id));
+    }
+    if id.eq_ignore_ascii_case("default") || uuid::Uuid::parse_str(id).is_ok() {
+        return Err(anyhow::anyhow!("Project ID '{}' is reserved", id));
+    }
+    Ok(())
+}
+
+/// Pick the files to leave out so the remaining files fit in `max_bytes`.
+/// Files are dropped in the order given by `policy` until the total size is within budget;
+/// a `max_bytes` of 0 means no limit. Returned in eviction order.
+pub fn files_to_evict(files: &[ProjectFile], max_bytes: u64, policy: EvictionPolicy) -> Vec<&ProjectFile> {
+    let mut total: u64 = files.iter().map(|f| f.size).sum();
+    if max_bytes == 0 || total <= max_bytes {
+        return Vec::new();
+    }
+
+    let mut candidates: Vec<&ProjectFile> = files.iter().collect();
+    match policy {
+        EvictionPolicy::Oldest => candidates.sort_by_key(|f| f.uploaded_at),
+        EvictionPolicy::Newest => candidates.sort_by_key(|f| std::cmp::Reverse(f.uploaded_at)),
+        EvictionPolicy::Largest => candidates.sort_by_key(|f| std::cmp::Reverse(f.size)),
+    }
+
+    let mut evicted = Vec::new();
+    for file in candidates {
+        if total <= max_bytes {
+            break;
+        }
+        total -= file.size;
+        evicted.push(file);
+    }
+    evicted
+}
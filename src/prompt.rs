@@ -2,67 +2,399 @@
 use serde_json::json;
 use std::path::Path;
 use std::fs;
+use std::time::{Duration, Instant};
 use regex::Regex;
 
-use crate::api::{create_api_client_from_config, format_prompt};
-use crate::utils::{clipboard, output, piping};
+use crate::capabilities::is_provider_model;
+use crate::api::{StreamError, create_api_client, create_api_client_with_effort, format_context_block, format_prompt, format_prompt_with_template, is_retryable_error};
+use crate::utils::{clipboard, format_check, json_repair, output, piping, redact};
 use crate::project::ProjectManager;
 use crate::models::Project;
+use crate::settings::{ContextWindowCheck, Settings};
 
+/// Output handling options shared by the structured reasoning entry points
+#[derive(Debug, Clone, Default)]
+pub struct PromptOptions {
+    /// Copy the final response to the clipboard
+    pub clipboard: bool,
+    /// Hide thinking blocks in the response
+    pub no_thinking: bool,
+    /// Extract and repair JSON from the response
+    pub json_repair: bool,
+    /// Fallback models overriding the configured `fallback_models`
+    pub fallback_models: Vec<String>,
+    /// Suppress status output so only the response is printed
+    pub quiet: bool,
+    /// Response language overriding the configured `response_language`
+    pub language: Option<String>,
+    /// Ask the configured error helper to explain a failed request
+    pub explain_errors: bool,
+    /// Log the unfiltered response for this run, as if `log_raw_response` were set
+    pub keep_thinking_in_log: bool,
+    /// Project file budget in bytes overriding `project_context.max_bytes`
+    pub context_budget: Option<u64>,
+    /// Recursion convergence threshold overriding `convergence_threshold`
+    pub convergence_threshold: Option<f64>,
+    /// Reasoning effort (low/medium/high) overriding the configured `reasoning_effort`
+    pub reasoning_effort: Option<String>,
+    /// Sampling temperature overriding the provider's `temperature`
+    pub temperature: Option<f64>,
+    /// Output token limit overriding the provider's `max_tokens`
+    pub max_tokens: Option<u32>,
+    /// Request timeout in seconds overriding `request_timeout_secs`; 0 waits indefinitely
+    pub timeout_secs: Option<u64>,
+    /// Shell command whose output is included as context
+    pub context_command: Option<String>,
+    /// Titles for the piped, command and clipboard context blocks, in that order
+    pub context_labels: Vec<String>,
+    /// Free-form labels recorded in the session log entry
+    pub tags: Vec<String>,
+    /// Keep launching recursion waves after one fails
+    pub continue_on_error: bool,
+    /// Keep the temporary files handed between recursion waves instead of deleting them
+    pub keep_tempfiles: bool,
+    /// Collapse whitespace in piped goals and context
+    pub collapse_whitespace: bool,
+    /// Configured provider to use instead of the active one for this run
+    pub provider: Option<String>,
+    /// Model to use instead of the provider's configured one for this run
+    pub model: Option<String>,
+    /// Context window check (warn/refuse/off) overriding `context_window_check`
+    pub context_window_check: Option<String>,
+    /// Treat piped stdin as context even when the goals are typed interactively
+    pub append_stdin: bool,
+    /// Fail when the response doesn't follow the requested return format
+    pub strict: bool,
+    /// Include the clipboard contents as context
+    pub attach_clipboard: bool,
+    /// Copy the final response to the clipboard without printing it
+    pub clipboard_only: bool,
+    /// Pull a missing Ollama model and retry, as if `auto_pull` were set for the provider
+    pub auto_pull: bool,
+    /// Ask for extended thinking, as if `extended_thinking` were set for the provider
+    pub extended_thinking: bool,
+    /// Regexes to redact from the prompt, on top of the configured `redact_patterns`
+    pub redact_patterns: Vec<String>,
+    /// Copy JSON responses indented, as they are displayed, rather than as received
+    pub pretty_clipboard: bool,
+    /// Append each streamed delta to this file as it arrives
+    pub stream_to_file: Option<std::path::PathBuf>,
+    /// Write the final response to this file once it has arrived
+    pub output_file: Option<std::path::PathBuf>,
+    /// Project file ids to include in a project prompt; empty includes every file
+    pub project_files: Vec<String>,
+    /// Ignore `.olaHints` and the configured `defaults` for this run
+    pub fresh: bool,
+    /// Refuse, rather than warn about, a request body over `max_request_bytes`
+    pub fail_fast: bool,
+    /// Print only the final answer once it has arrived, with thinking blocks removed
+    pub answer_only: bool,
+    /// Print the prompt as sent to stderr once the request has succeeded
+    pub show_prompt: bool,
+    /// Print the assembled prompt to stdout instead of sending it
+    pub dry_run: bool,
+}
+
+impl PromptOptions {
+    // Settings for this run; --fresh drops the configured defaults
+    fn settings(&self) -> Settings {
+        let mut settings = crate::settings::Settings::load().unwrap_or_default();
+        if self.fresh {
+            settings.defaults = crate::settings::DefaultSettings::default();
+        }
+        settings
+    }
+
+    // Fallback models from the command line, or the configured ones if none were given
+    fn fallback_models<'a>(&'a self, settings: &'a Settings) -> &'a [String] {
+        if self.fallback_models.is_empty() {
+            &settings.fallback_models
+        } else {
+            &self.fallback_models
+        }
+    }
 
-/// Main function for structured reasoning with <think> blocks
+    // Project context limits, with the command-line budget taking precedence
+    fn project_context(&self, settings: &Settings) -> crate::settings::ProjectContextSettings {
+        let mut project_context = settings.project_context.clone();
+        if let Some(max_bytes) = self.context_budget {
+            project_context.max_bytes = max_bytes;
+        }
+        project_context
+    }
+
+    // Whether the session log should include the unfiltered response
+    fn log_raw_response(&self, settings: &Settings) -> bool {
+        self.keep_thinking_in_log || settings.behavior.log_raw_response
+    }
+
+    /// Convergence threshold from the command line, or the configured one if none was given.
+    /// Returns `None` when the check is disabled.
+    pub fn convergence_threshold(&self, settings: &Settings) -> Option<f64> {
+        let threshold = self.convergence_threshold.unwrap_or(settings.behavior.convergence_threshold);
+        (threshold > 0.0).then_some(threshold)
+    }
+
+    // Reasoning effort from the command line, or the configured one if none was given
+    fn reasoning_effort<'a>(&'a self, settings: &'a Settings) -> Option<&'a str> {
+        let effort = self.reasoning_effort.as_deref().unwrap_or(&settings.defaults.reasoning_effort);
+        (!effort.is_empty()).then_some(effort)
+    }
+
+    // Provider for this run: the chosen one if set, otherwise the active one
+    fn provider_config(&self, config: &crate::config::Config) -> Result<crate::config::ProviderConfig, std::io::Error> {
+        let mut provider_config = match &self.provider {
+            Some(name) => config.get_provider(name).ok_or_else(|| {
+                let configured: Vec<&str> = config.providers.iter().map(|p| p.provider.as_str()).collect();
+                let message = if configured.is_empty() {
+                    format!("Provider '{}' is not configured, and no providers are. Run 'ola configure' first.", name)
+                } else {
+                    format!("Provider '{}' is not configured. Configured providers: {}", name, configured.join(", "))
+                };
+                std::io::Error::new(std::io::ErrorKind::NotFound, message)
+            }),
+            None => config.get_active_provider().ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "No active provider configured. Run 'ola configure' first.",
+                )
+            }),
+        }?;
+        if let Some(model) = &self.model {
+            provider_config.model = Some(model.clone());
+        }
+        // Command-line overrides that reach the client through additional_settings
+        let overrides = [
+            ("auto_pull", self.auto_pull.then_some(serde_json::Value::Bool(true))),
+            ("extended_thinking", self.extended_thinking.then_some(serde_json::Value::Bool(true))),
+            ("stream_to_file", self.stream_to_file.as_ref().map(|path| serde_json::json!(path))),
+            ("temperature", self.temperature.map(|temperature| serde_json::json!(temperature))),
+            ("max_tokens", self.max_tokens.map(|max_tokens| serde_json::json!(max_tokens))),
+            ("request_timeout_secs", self.timeout_secs.map(|secs| serde_json::json!(secs))),
+        ];
+        for (key, value) in overrides {
+            let Some(value) = value else { continue };
+            let settings = provider_config.additional_settings.get_or_insert_with(|| serde_json::json!({}));
+            if let Some(settings) = settings.as_object_mut() {
+                settings.insert(key.to_string(), value);
+            }
+        }
+        Ok(provider_config)
+    }
+
+    // Redaction patterns for this run: the configured ones plus any from the command line
+    fn redact_patterns(&self, settings: &Settings) -> Vec<String> {
+        settings.redact_patterns.iter().chain(&self.redact_patterns).cloned().collect()
+    }
+
+    // Context window check from the command line, or the configured one if none was given
+    fn context_window_check(&self, settings: &Settings) -> ContextWindowCheck {
+        match self.context_window_check.as_deref() {
+            Some("warn") => ContextWindowCheck::Warn,
+            Some("refuse") => ContextWindowCheck::Refuse,
+            Some("off") => ContextWindowCheck::Off,
+            _ => settings.behavior.context_window_check,
+        }
+    }
+
+    // Response language from the command line, or the configured one if none was given
+    fn language<'a>(&'a self, settings: &'a Settings) -> &'a str {
+        self.language.as_deref().unwrap_or(&settings.defaults.response_language)
+    }
+}
+
+// Common language codes accepted by --lang and `response_language`
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("nl", "Dutch"),
+    ("ru", "Russian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("zh", "Chinese"),
+];
+
+/// Resolve a language code or name to the name used in the response instruction.
+/// Returns `None` for an empty value or "none"; unknown values are used as given.
+pub fn language_name(language: &str) -> Option<String> {
+    let language = language.trim();
+    if language.is_empty() || language.eq_ignore_ascii_case("none") {
+        return None;
+    }
+
+    let code = language.to_lowercase();
+    let name = LANGUAGE_NAMES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| language.to_string());
+    Some(name)
+}
+
+// Append the response language instruction, if any, as its own line
+fn append_language_instruction(input_data: &mut String, language: &str) {
+    if let Some(name) = language_name(language) {
+        input_data.push_str(&format!("\nRespond in {}.", name));
+    }
+}
+
+/// Similarity between two responses from 0.0 (unrelated) to 1.0 (identical),
+/// ignoring case and whitespace differences
+pub fn response_similarity(a: &str, b: &str) -> f64 {
+    let normalize = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return 1.0;
+    }
+    similar::TextDiff::from_words(&a, &b).ratio() as f64
+}
+
+/// Main function for structured reasoning with <think> blocks.
+/// Returns the final response.
 pub fn structure_reasoning(
     goals: &str,
     return_type: &str,
     warnings: &str,
-    clipboard: bool,
     context: Option<&str>,
-    no_thinking: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
+    options: &PromptOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
     // Try to load settings
-    let settings = crate::settings::Settings::load().unwrap_or_default();
-    
-    // Format the prompt with goals, return type, warnings, and optional context
-    let mut input_data = format_prompt(goals, return_type, warnings, context);
+    let settings = options.settings();
     
-    // Read and append hints if available
-    append_hints_if_available(&mut input_data)?;
+    // Assemble the prompt with goals, return type, warnings, context, and hints
+    let input_data = build_prompt(&settings, goals, return_type, warnings, context, options)?;
+    if options.dry_run {
+        println!("{}", input_data);
+        return Ok(input_data);
+    }
+    check_request_size(&input_data, &prompt_parts(goals, return_type, warnings, context), &settings, options.fail_fast)?;
     
     // Load current configuration and create API client
-    let api_client = create_api_client_from_config()?;
-    
-    // Use model from config, settings, or fallback to default
     let config = crate::config::Config::load()?;
-    let provider_config = config.get_active_provider().ok_or_else(|| {
-        std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No active provider configured. Run 'ola configure' first.",
-        )
-    })?;
+    let provider_config = options.provider_config(&config)?;
+    let api_client = create_api_client_with_effort(&provider_config, options.reasoning_effort(&settings), &settings)?;
     
-    let model = provider_config
-        .model
-        .as_deref()
-        .unwrap_or(&settings.default_model);
-    output::println_colored(&format!("🧠 Using model: {}", model), output::Color::BrightBlue);
+    // Use model from config, settings, or fallback to default
+    let model = resolve_model(&provider_config, &settings, &config)?;
+    if !options.quiet {
+        output::eprintln_colored(&format!("🧠 Using model: {}", model), output::Color::BrightBlue);
+    }
     
-    // Stream the response
-    let response = stream_response(&api_client, &input_data, model, no_thinking)?;
+    // Stream the response, falling back to alternate models on retryable failures
+    let pretty = show_pretty_json(return_type, options);
+    let display = !options.clipboard_only && !pretty && !options.answer_only && !options.json_repair;
+    let result = stream_with_fallback(&api_client, &input_data, model, options.fallback_models(&settings), options.no_thinking, options.quiet, display, options.context_window_check(&settings))?;
+    if pretty {
+        print_json_response(&result.content);
+    }
+    if options.show_prompt {
+        print_sent_prompt(&input_data, &result.model);
+    }
+    // --answer-only drops thinking blocks whether or not the model is known to emit them
+    let content = if options.answer_only { strip_thinking_blocks(&result.content)? } else { result.content };
+    let response = finalize_response(content, options);
+    if options.answer_only && !options.clipboard_only {
+        println!("{}", response.trim());
+    }
     
     // Handle clipboard copy if requested
-    if clipboard {
-        match clipboard::copy_to_clipboard(&response) {
-            Ok(_) => output::print_success("Response copied to clipboard"),
-            Err(e) => output::print_error(&format!("Failed to copy to clipboard: {}", e))
-        }
+    if options.clipboard || options.clipboard_only {
+        copy_response(&clipboard_text(&response, options), options.quiet);
+    }
+    if let Some(path) = &options.output_file {
+        write_output_file(path, &response)?;
     }
     
     // Log session if enabled in settings
     if settings.behavior.enable_logging {
-        log_session(goals, return_type, warnings, model, &response)?;
+        let raw_response = options.log_raw_response(&settings).then_some(result.raw.as_str());
+        log_session(goals, return_type, warnings, &result.model, &response, raw_response, result.finish_reason.as_deref(), &options.tags)?;
     }
     
-    Ok(())
+    check_compliance(return_type, &response, options)?;
+    Ok(response)
+}
+
+// Rough characters per token, used to estimate throughput without provider usage counts
+const CHARS_PER_TOKEN: f64 = 4.0;
+
+/// Latency and throughput over repeated runs of the same prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkSummary {
+    pub runs: usize,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub p95: Duration,
+    /// Estimated from response length, so only comparable between runs of this tool
+    pub tokens_per_sec: f64,
+}
+
+impl BenchmarkSummary {
+    /// Summarize (latency, response length in characters) samples; `None` if there are none
+    pub fn from_samples(samples: &[(Duration, usize)]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut latencies: Vec<Duration> = samples.iter().map(|(latency, _)| *latency).collect();
+        latencies.sort();
+        let total: Duration = latencies.iter().sum();
+        // Nearest-rank percentile
+        let p95_index = ((latencies.len() as f64 * 0.95).ceil() as usize).saturating_sub(1);
+        let tokens: f64 = samples.iter().map(|(_, chars)| *chars as f64 / CHARS_PER_TOKEN).sum();
+        let tokens_per_sec = if total.is_zero() { 0.0 } else { tokens / total.as_secs_f64() };
+
+        Some(Self {
+            runs: samples.len(),
+            min: latencies[0],
+            max: latencies[latencies.len() - 1],
+            mean: total / samples.len() as u32,
+            p95: latencies[p95_index],
+            tokens_per_sec,
+        })
+    }
+}
+
+/// Send the structured prompt `runs` times without printing the responses, timing each request
+pub fn benchmark(
+    goals: &str,
+    return_type: &str,
+    warnings: &str,
+    options: &PromptOptions,
+    runs: u32,
+) -> Result<BenchmarkSummary, Box<dyn std::error::Error>> {
+    let settings = options.settings();
+    let input_data = build_prompt(&settings, goals, return_type, warnings, None, options)?;
+    check_request_size(&input_data, &prompt_parts(goals, return_type, warnings, None), &settings, options.fail_fast)?;
+
+    let config = crate::config::Config::load()?;
+    let provider_config = options.provider_config(&config)?;
+    let api_client = create_api_client_with_effort(&provider_config, options.reasoning_effort(&settings), &settings)?;
+    let model = resolve_model(&provider_config, &settings, &config)?;
+    if !options.quiet {
+        output::println_colored(&format!("⏱️  Benchmarking {} on {} ({} runs)", model, api_client.provider_name(), runs), output::Color::BrightBlue);
+    }
+
+    check_context_window(&api_client, model, &input_data, options.context_window_check(&settings))?;
+    let mut samples = Vec::new();
+    for run in 1..=runs {
+        let started = Instant::now();
+        let completion = api_client.send_prompt(&input_data, model)?;
+        let latency = started.elapsed();
+        if !options.quiet {
+            println!("Run {}/{}: {} ms", run, runs, latency.as_millis());
+        }
+        samples.push((latency, completion.content.chars().count()));
+    }
+
+    BenchmarkSummary::from_samples(&samples).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "Benchmark needs at least one run").into()
+    })
 }
 
 /// Stream raw prompt without structured reasoning
@@ -71,57 +403,60 @@ pub fn stream_non_think(
     clipboard: bool,
     context: Option<&str>,
     filter_thinking: bool,
+    quiet: bool,
+    output_file: Option<&Path>,
+    timeout_secs: Option<u64>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Try to load settings
     let settings = crate::settings::Settings::load().unwrap_or_default();
-    
-    // Format the prompt with optional context
-    let mut input_data = if let Some(ctx) = context {
-        format!("{}\nContext: {}", prompt, ctx)
-    } else {
-        prompt.to_string()
-    };
-    
-    // Read and append hints if available
-    append_hints_if_available(&mut input_data)?;
-    
-    // Create API client
-    let api_client = create_api_client_from_config()?;
+    let input_data = build_non_think_prompt(&settings, prompt, context)?;
     
     // Get model information
     let config = crate::config::Config::load()?;
-    let provider_config = config.get_active_provider().ok_or_else(|| {
+    let mut provider_config = config.get_active_provider().ok_or_else(|| {
         std::io::Error::new(
             std::io::ErrorKind::NotFound,
             "No active provider configured. Run 'ola configure' first.",
         )
     })?;
+    if let Some(secs) = timeout_secs {
+        let settings = provider_config.additional_settings.get_or_insert_with(|| serde_json::json!({}));
+        if let Some(settings) = settings.as_object_mut() {
+            settings.insert("request_timeout_secs".to_string(), serde_json::json!(secs));
+        }
+    }
     
-    let model = provider_config
-        .model
-        .as_deref()
-        .unwrap_or(&settings.default_model);
-    output::println_colored(&format!("🧠 Using model: {}", model), output::Color::BrightBlue);
+    // Create API client
+    let api_client = create_api_client_with_effort(&provider_config, None, &settings)?;
+    
+    let model = resolve_model(&provider_config, &settings, &config)?;
+    if !quiet {
+        output::eprintln_colored(&format!("🧠 Using model: {}", model), output::Color::BrightBlue);
+    }
     
     // Stream the response
-    let response = stream_response(&api_client, &input_data, model, filter_thinking)?;
+    let result = stream_response(&api_client, &input_data, model, filter_thinking, quiet, true, settings.behavior.context_window_check)?;
+    let response = result.content;
     
     // Handle clipboard copy if requested
     if clipboard {
-        match clipboard::copy_to_clipboard(&response) {
-            Ok(_) => output::print_success("Response copied to clipboard"),
-            Err(e) => output::print_error(&format!("Failed to copy to clipboard: {}", e))
-        }
+        copy_response(&response, quiet);
+    }
+    if let Some(path) = output_file {
+        write_output_file(path, &response)?;
     }
     
     // Log session if enabled in settings
     if settings.behavior.enable_logging {
-        let log_entry = json!({
+        let mut log_entry = json!({
             "timestamp": chrono::Utc::now().to_rfc3339(),
             "prompt": prompt,
             "model": model,
             "output_length": response.len(),
         });
+        if settings.behavior.log_raw_response {
+            log_entry["raw_response"] = json!(result.raw);
+        }
         
         if let Err(e) = piping::append_to_log(&settings.behavior.log_file, &log_entry.to_string()) {
             eprintln!("Failed to log session: {}", e);
@@ -131,73 +466,637 @@ pub fn stream_non_think(
     Ok(())
 }
 
-// Helper function to stream response with thinking block filtering if needed
+/// Ask the configured error helper provider to explain a failed request.
+/// The helper is called directly, so its own failures are returned rather than explained.
+pub fn explain_error(
+    config: &crate::config::Config,
+    settings: &Settings,
+    error_text: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let helper = &settings.error_helper;
+    if helper.provider.trim().is_empty() {
+        let settings_path = crate::profile::ola_dir()
+            .map(|dir| dir.join("settings.yaml").display().to_string())
+            .unwrap_or_else(|_| "~/.ola/settings.yaml".to_string());
+        return Err(format!("No error helper configured; set error_helper.provider in {}", settings_path).into());
+    }
+
+    let provider_config = config.get_provider(&helper.provider).ok_or_else(|| {
+        format!("Error helper provider '{}' is not configured. Run 'ola configure' first.", helper.provider)
+    })?;
+    let model = if !helper.model.trim().is_empty() {
+        helper.model.clone()
+    } else if let Some(model) = provider_config.model.clone() {
+        model
+    } else {
+        crate::config::provider_info(&provider_config.provider)
+            .map(|info| info.default_model.to_string())
+            .unwrap_or_else(|| settings.default_model.clone())
+    };
+
+    let prompt = format!(
+        "A request made by the ola command-line tool to an AI provider failed with the error below. \
+         Explain briefly what most likely went wrong and how to fix it.\n\nError: {}",
+        error_text
+    );
+    let api_client = create_api_client(&provider_config)?;
+    Ok(api_client.send_prompt(&prompt, &model)?.content)
+}
+
+/// The prompt `structure_reasoning` sends for these options: the structured prompt with
+/// hints unless `fresh` is set, the language instruction, and redaction applied
+pub fn build_prompt(
+    settings: &Settings,
+    goals: &str,
+    return_type: &str,
+    warnings: &str,
+    context: Option<&str>,
+    options: &PromptOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut input_data = format_prompt_with_template(&settings.prompt_template, goals, return_type, warnings, context);
+    if !options.fresh {
+        append_hints_if_available(&mut input_data)?;
+    }
+    append_language_instruction(&mut input_data, options.language(settings));
+    redact_prompt(&input_data, &options.redact_patterns(settings))
+}
+
+/// The prompt `stream_non_think` sends: the raw prompt with optional context, hints and
+/// the language instruction, redacted
+pub fn build_non_think_prompt(settings: &Settings, prompt: &str, context: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    let mut input_data = if let Some(ctx) = context {
+        let template = &settings.prompt_template;
+        format!("{}{}", prompt, format_context_block(template, &template.context_label, ctx))
+    } else {
+        prompt.to_string()
+    };
+    append_hints_if_available(&mut input_data)?;
+    append_language_instruction(&mut input_data, &settings.defaults.response_language);
+    redact_prompt(&input_data, &settings.redact_patterns)
+}
+
+/// The prompt `structure_reasoning_with_project` sends: project goals, contexts and files
+/// with the run's goals and context, then the format, warnings and hints, redacted
+pub fn build_project_run_prompt(
+    settings: &Settings,
+    project: &Project,
+    goals: &str,
+    return_type: &str,
+    warnings: &str,
+    context: Option<&str>,
+    options: &PromptOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let enhanced_prompt = project_request(settings, project, goals, context, options)?;
+    complete_project_prompt(settings, &enhanced_prompt, return_type, warnings, options)
+}
+
+// Project goals, contexts and files followed by the run's goals and additional context
+fn project_request(
+    settings: &Settings,
+    project: &Project,
+    goals: &str,
+    context: Option<&str>,
+    options: &PromptOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut enhanced_prompt = build_project_prompt(project, Some(goals), &settings.prompt_template, &options.project_context(settings))?;
+    if let Some(ctx) = context {
+        let template = &settings.prompt_template;
+        enhanced_prompt.push_str(&format_context_block(template, &template.additional_context_label, ctx));
+    }
+    Ok(enhanced_prompt)
+}
+
+// Add the format, warnings, hints and language instruction to a project request, redacted
+fn complete_project_prompt(
+    settings: &Settings,
+    enhanced_prompt: &str,
+    return_type: &str,
+    warnings: &str,
+    options: &PromptOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut input_data = format_prompt(enhanced_prompt, return_type, warnings, None);
+    if !options.fresh {
+        append_hints_if_available(&mut input_data)?;
+    }
+    append_language_instruction(&mut input_data, options.language(settings));
+    redact_prompt(&input_data, &options.redact_patterns(settings))
+}
+
+// Model for a run: the provider's configured one, or else the global `default_model`.
+// A global default that can't belong to the provider (say gpt-5 for Ollama) stops the run
+// with the models that would work, rather than letting the provider answer with a bare 404.
+fn resolve_model<'a>(
+    provider_config: &'a crate::config::ProviderConfig,
+    settings: &'a Settings,
+    config: &crate::config::Config,
+) -> Result<&'a str, Box<dyn std::error::Error>> {
+    if let Some(model) = provider_config.model.as_deref() {
+        return Ok(model);
+    }
+    let model = settings.default_model.as_str();
+    let provider = provider_config.provider.as_str();
+    let valid: Vec<String> = match is_provider_model(provider, model) {
+        Some(true) => return Ok(model),
+        Some(false) => crate::config::builtin_models(provider).iter().map(|m| m.to_string()).collect(),
+        None if provider == "Ollama" => {
+            // When the installed models can't be listed, let the request report the problem
+            let Ok(installed) = crate::config::fetch_ollama_installed_models(&crate::config::ollama_base_url(config)) else {
+                return Ok(model);
+            };
+            let names: Vec<String> = installed.into_iter().map(|m| m.name).collect();
+            if names.iter().any(|name| name == model || name.strip_suffix(":latest") == Some(model)) {
+                return Ok(model);
+            }
+            names
+        }
+        None => return Ok(model),
+    };
+    let available = if valid.is_empty() { "none".to_string() } else { valid.join(", ") };
+    Err(format!(
+        "{} has no model configured and the default_model '{}' is not one of its models (available: {}). Set one with 'ola configure --provider {} --model <MODEL>'",
+        provider, model, available, provider
+    ).into())
+}
+
+// Replace secrets in an assembled prompt with [REDACTED], noting on stderr how many were found
+fn redact_prompt(prompt: &str, patterns: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+    let (redacted, count) = redact::redact(prompt, patterns)?;
+    if count > 0 {
+        eprintln!("🔒 Redacted {} secret{} from the prompt", count, if count == 1 { "" } else { "s" });
+    }
+    Ok(redacted)
+}
+
+/// Warnings to use for a prompt: the explicit value if given, otherwise the configured default
+pub fn warnings_or_default<'a>(settings: &'a Settings, warnings: Option<&'a str>) -> &'a str {
+    warnings.unwrap_or(&settings.defaults.warnings)
+}
+
+/// Read a long-form flag value (e.g. `--format-file`, `--warnings-file`) from disk
+pub fn read_spec_file(path: &Path) -> Result<String, std::io::Error> {
+    let content = fs::read_to_string(path)?;
+    Ok(content.trim_end().to_string())
+}
+
+// JSON responses are collected and shown indented instead of streamed, unless the output
+// is meant for a script (--quiet), not shown at all, or shown by --json-repair instead
+fn show_pretty_json(return_type: &str, options: &PromptOptions) -> bool {
+    !options.quiet && !options.clipboard_only && !options.json_repair && format_check::is_json_format(return_type)
+}
+
+// Print a collected response, indenting it when it is valid JSON and leaving anything else as is
+fn print_json_response(content: &str) {
+    match output::format_json(content, atty::is(atty::Stream::Stdout)) {
+        Some(pretty) => println!("{}\n", pretty),
+        None => println!("{}\n", content.trim()),
+    }
+}
+
+// What goes on the clipboard: the response as received, or indented with --pretty-clipboard
+fn clipboard_text(response: &str, options: &PromptOptions) -> String {
+    options.pretty_clipboard
+        .then(|| output::format_json(response, false))
+        .flatten()
+        .unwrap_or_else(|| response.to_string())
+}
+
+// Copy a response to the clipboard; the confirmation is status output, so --quiet hides it
+fn copy_response(response: &str, quiet: bool) {
+    match clipboard::copy_to_clipboard(response) {
+        Ok(_) if !quiet => output::print_success("Response copied to clipboard"),
+        Ok(_) => {}
+        Err(e) => output::print_error(&format!("Failed to copy to clipboard: {}", e)),
+    }
+}
+
+// Echo the prompt exactly as it was sent, after redaction, to stderr
+fn print_sent_prompt(prompt: &str, model: &str) {
+    output::clear_line();
+    eprintln!("--- Prompt sent to {} ---", model);
+    eprintln!("{}", prompt);
+    eprintln!("--- End of prompt ---");
+}
+
+/// Write a response to `path` with a trailing newline, creating parent directories
+pub fn write_output_file(path: &Path, response: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let text = if response.ends_with('\n') { response.to_string() } else { format!("{}\n", response) };
+    fs::write(path, text).map_err(|e| format!("Failed to write response to {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+// Apply requested post-processing to a received response. With --json-repair the response
+// isn't streamed, so the repaired JSON (or the raw response if repair fails) is printed here
+fn finalize_response(response: String, options: &PromptOptions) -> String {
+    if !options.json_repair {
+        return response;
+    }
+
+    let shown = !options.clipboard_only && !options.answer_only;
+    match json_repair::repair_json(&response) {
+        Ok(value) => {
+            let cleaned = serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string());
+            if shown {
+                if !options.quiet {
+                    output::eprintln_colored("🔧 Repaired JSON:", output::Color::BrightCyan);
+                }
+                println!("{}", cleaned);
+            }
+            cleaned
+        }
+        Err(e) => {
+            eprintln!("Warning: JSON repair failed ({}); keeping raw response", e);
+            if shown {
+                println!("{}\n", response.trim());
+            }
+            response
+        }
+    }
+}
+
+// Report whether the response follows the requested return format; with --strict a
+// response that doesn't is an error
+fn check_compliance(return_type: &str, response: &str, options: &PromptOptions) -> Result<(), Box<dyn std::error::Error>> {
+    match format_check::check_format(return_type, response) {
+        Some(Ok(())) if !options.quiet => {
+            output::eprintln_colored(&format!("✅ Response follows the {} format", format_label(return_type)), output::Color::BrightGreen);
+        }
+        None | Some(Ok(())) => {}
+        Some(Err(problem)) if options.strict => {
+            return Err(format!("Response does not follow the requested format: {}", problem).into());
+        }
+        Some(Err(problem)) => {
+            eprintln!("⚠️  Response does not follow the requested format: {}", problem);
+        }
+    }
+    Ok(())
+}
+
+// Short name for a return format in compliance notes; shapes are too long to repeat
+fn format_label(return_type: &str) -> &str {
+    let return_type = return_type.trim();
+    if return_type.starts_with(['{', '[']) {
+        "requested JSON shape"
+    } else {
+        return_type
+    }
+}
+
+/// Stream a response from the primary model, trying each fallback model in turn
+/// when the previous one fails with a retryable error. The returned result records
+/// which model actually produced the response. With `display` off nothing is printed
+/// to stdout.
+#[allow(clippy::too_many_arguments)]
+pub fn stream_with_fallback(
+    api_client: &crate::api::ApiClient,
+    prompt: &str,
+    model: &str,
+    fallback_models: &[String],
+    filter_thinking: bool,
+    quiet: bool,
+    display: bool,
+    context_check: ContextWindowCheck,
+) -> Result<PromptResult, Box<dyn std::error::Error>> {
+    let mut candidates = std::iter::once(model)
+        .chain(fallback_models.iter().map(String::as_str))
+        .peekable();
+
+    while let Some(current) = candidates.next() {
+        match stream_response(api_client, prompt, current, filter_thinking, quiet, display, context_check) {
+            Ok(result) => return Ok(result),
+            Err(e) => match candidates.peek() {
+                Some(next) if is_retryable_error(e.as_ref()) => {
+                    output::clear_line();
+                    eprintln!("Warning: model {} failed ({}); falling back to {}", current, e, next);
+                }
+                _ => return Err(e),
+            },
+        }
+    }
+
+    unreachable!("the primary model is always attempted")
+}
+
+// Helper function to stream response with thinking block filtering if needed.
+// When thinking blocks will be stripped, the response is fetched whole and only the
+// filtered text is printed, so what the user sees matches what is copied and logged.
+// With `display` off the response is fetched whole and not printed at all.
 fn stream_response(
     api_client: &crate::api::ApiClient,
     prompt: &str,
     model: &str,
-    filter_thinking: bool
-) -> Result<String, Box<dyn std::error::Error>> {
-    // Show loading animation while waiting for response
-    output::print_wave_animation(0, "Generating response");
-    std::thread::sleep(std::time::Duration::from_millis(500));
-    
-    // Add some visual feedback for the request
-    output::clear_line();
-    output::println_colored("⚡ Sending prompt to AI...", output::Color::BrightYellow);
+    filter_thinking: bool,
+    quiet: bool,
+    display: bool,
+    context_check: ContextWindowCheck,
+) -> Result<PromptResult, Box<dyn std::error::Error>> {
+    check_context_window(api_client, model, prompt, context_check)?;
+    let visible_thinking = api_client.visible_thinking(model);
+    let buffer_output = !display || filter_thinking;
+
+    if !quiet {
+        // Show loading animation while waiting for response
+        if filter_thinking {
+            output::print_thinking_animation(0, "Thinking");
+        } else {
+            output::print_wave_animation(0, "Generating response");
+        }
+        output::animation_pause(500);
+        
+        // Add some visual feedback for the request
+        output::clear_line();
+        output::eprintln_colored(&format!("⚡ Sending prompt to {}...", api_client.provider_name()), output::Color::BrightYellow);
+    }
     
-    // Get the raw response
-    let response = api_client.stream_prompt(prompt, model)?;
+    // Get the raw response; a response only shown once complete still streams into
+    // the --stream-to-file file
+    let result = if !buffer_output {
+        api_client.stream_prompt(prompt, model)
+    } else if let Some(mut file) = api_client.file_sink() {
+        api_client.stream_prompt_to(prompt, model, &mut file)
+    } else {
+        api_client.send_prompt(prompt, model)
+    };
+    // A stream that breaks off after some output still yields what arrived
+    let completion = match result {
+        Ok(completion) => completion,
+        Err(e) => match e.downcast::<StreamError>() {
+            Ok(stream_error) if !stream_error.partial.content.is_empty() => {
+                output::clear_line();
+                eprintln!("\n⚠️  {}; keeping the partial response", stream_error);
+                stream_error.partial
+            }
+            Ok(stream_error) => return Err(stream_error),
+            Err(e) => return Err(e),
+        },
+    };
+    if completion.is_truncated() {
+        eprintln!("⚠️  Response truncated at max_tokens; raise --max-tokens (or max_tokens in the provider's additional_settings)");
+    }
+    let raw = completion.content;
     
-    // Clear and show completion
-    output::println_colored("✨ Response received!", output::Color::BrightGreen);
-    println!(); // Add some space before output
+    if !quiet {
+        // Clear and show completion
+        output::eprintln_colored("✨ Response received!", output::Color::BrightGreen);
+        eprintln!(); // Add some space before output
+    }
     
     // If we need to filter thinking blocks, process the response
-    if filter_thinking {
-        output::println_colored("🔄 Filtering thinking blocks...", output::Color::BrightCyan);
-        // Use regex to remove thinking blocks
-        let re = Regex::new(r"<think>.*?</think>")?;
-        let filtered_response = re.replace_all(&response, "").to_string();
-        Ok(filtered_response)
+    let content = if filter_thinking {
+        filter_visible_thinking(visible_thinking, model, raw.clone(), quiet)?
     } else {
-        Ok(response)
+        raw.clone()
+    };
+    if buffer_output && display {
+        println!("{}\n", content.trim());
     }
+
+    Ok(PromptResult { content, raw, model: model.to_string(), finish_reason: completion.finish_reason })
 }
 
-// Helper function to read and append hints from .olaHints file
-fn append_hints_if_available(input_data: &mut String) -> Result<(), Box<dyn std::error::Error>> {
-    // Try to read hints from a local .olaHints file; if not found, fallback to global hints
-    let mut hints = String::new();
-    
-    // Check local file .olaHints in the current directory
-    if Path::new("./.olaHints").exists() {
-        hints = fs::read_to_string("./.olaHints")?;
+// Rough token count of a prompt, using the same characters-per-token ratio as the benchmark
+fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as f64 / CHARS_PER_TOKEN).ceil() as u64
+}
+
+// Warn about, or refuse, a prompt that is estimated to be longer than the model's
+// known context window, before the provider rejects it. Ollama doesn't reject it but
+// silently drops the start, so its configured `num_ctx` is checked the same way.
+fn check_context_window(api_client: &crate::api::ApiClient, model: &str, prompt: &str, check: ContextWindowCheck) -> Result<(), Box<dyn std::error::Error>> {
+    if check == ContextWindowCheck::Off {
+        return Ok(());
+    }
+    let Some(window) = api_client.context_window(model) else {
+        return Ok(());
+    };
+    let estimate = estimate_tokens(prompt);
+    if estimate <= window {
+        return Ok(());
+    }
+    let message = format!("prompt is about {} tokens, more than the {}-token context window of {}", estimate, window, model);
+    if check == ContextWindowCheck::Refuse {
+        return Err(format!("Not sending: {} (context_window_check is refuse)", message).into());
+    }
+    output::clear_line();
+    if api_client.provider_name() == "Ollama" {
+        eprintln!("⚠️  Warning: {}; Ollama will drop the start of it (raise num_ctx in the provider's additional_settings)", message);
     } else {
-        // Fallback to global hints in ~/.ola-hints/olaHints
-        if let Ok(home) = std::env::var("HOME") {
-            let global_path = format!("{}/.ola-hints/olaHints", home);
-            if Path::new(&global_path).exists() {
-                hints = fs::read_to_string(global_path)?;
+        eprintln!("⚠️  Warning: {}; the provider may reject it", message);
+    }
+    Ok(())
+}
+
+// Request body limit used when `max_request_bytes` is 0
+const REQUEST_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+// Warn about, or with --fail-fast refuse, a request whose body would exceed `max_request_bytes`,
+// naming the largest of the parts the prompt was assembled from
+fn check_request_size(prompt: &str, parts: &[(String, usize)], settings: &Settings, fail_fast: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let max_bytes = match settings.behavior.max_request_bytes {
+        0 => REQUEST_MAX_BYTES,
+        bytes => bytes,
+    };
+    // The prompt travels as a JSON string, so escaped characters count at their escaped size
+    let bytes = serde_json::to_string(prompt).map_or(prompt.len(), |body| body.len()) as u64;
+    if bytes <= max_bytes {
+        return Ok(());
+    }
+    let mut message = format!("request body is about {} bytes, over the {}-byte max_request_bytes", bytes, max_bytes);
+    if let Some((name, size)) = parts.iter().max_by_key(|(_, size)| *size) {
+        message.push_str(&format!("; the largest part is {} ({} bytes)", name, size));
+    }
+    if fail_fast {
+        return Err(format!("Not sending: {} (--fail-fast)", message).into());
+    }
+    output::clear_line();
+    eprintln!("⚠️  Warning: {}", message);
+    Ok(())
+}
+
+// Named parts of a structured prompt and their sizes
+fn prompt_parts(goals: &str, return_type: &str, warnings: &str, context: Option<&str>) -> Vec<(String, usize)> {
+    let mut parts = vec![
+        ("the goals".to_string(), goals.len()),
+        ("the return format".to_string(), return_type.len()),
+        ("the warnings".to_string(), warnings.len()),
+    ];
+    if let Some(context) = context {
+        parts.push(("the context".to_string(), context.len()));
+    }
+    parts
+}
+
+// Named parts of a project prompt and their sizes, with each included file counted as sent
+fn project_prompt_parts(project: &Project, goals: &str, context: Option<&str>, project_context: &crate::settings::ProjectContextSettings) -> Vec<(String, usize)> {
+    let mut parts = prompt_parts(goals, "", "", context);
+    parts.push(("the project goals".to_string(), project.goals.iter().map(|goal| goal.text.len()).sum()));
+    parts.push(("the project contexts".to_string(), project.contexts.iter().map(|context| context.text.len()).sum()));
+    let evicted = crate::project::files_to_evict(&project.files, project_context.max_bytes, project_context.eviction_policy);
+    for file in project.files.iter().filter(|f| !evicted.iter().any(|e| e.id == f.id)) {
+        let sent = (file.size.min(usize::MAX as u64) as usize).min(PROJECT_FILE_MAX_BYTES);
+        parts.push((format!("file {}", file.filename), sent));
+    }
+    parts
+}
+
+// Strip `<think>` blocks from the response. Models the client reports as keeping their
+// reasoning hidden can still emit them behind an OpenAI-compatible `base_url`, so the
+// note about hidden thinking is only shown when there was nothing to strip.
+fn filter_visible_thinking(visible_thinking: bool, model: &str, response: String, quiet: bool) -> Result<String, Box<dyn std::error::Error>> {
+    if visible_thinking && !quiet {
+        output::eprintln_colored("🔄 Filtering thinking blocks...", output::Color::BrightCyan);
+    }
+    let filtered = strip_thinking_blocks(&response)?;
+    if !visible_thinking && !quiet && filtered == response {
+        output::eprintln_colored(
+            &format!("ℹ️  {} does not emit visible thinking; nothing to filter", model),
+            output::Color::BrightBlue,
+        );
+    }
+    Ok(filtered)
+}
+
+/// Strip all `<think>...</think>` blocks from the text, including ones spanning several
+/// lines, along with the blank lines that followed them
+pub fn strip_thinking_blocks(text: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let re = Regex::new(r"(?s)<think>.*?</think>\s*")?;
+    Ok(re.replace_all(text, "").to_string())
+}
+
+/// Session defaults and notes read from an `.olaHints` file.
+/// Files in the `[Goals, Return Format, Warnings]` format fill the matching fields;
+/// any text outside those sections is kept as free-form notes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionHints {
+    pub goals: Option<String>,
+    pub return_format: Option<String>,
+    pub warnings: Option<String>,
+    pub notes: String,
+}
+
+// Section headers of the structured hints format
+const HINT_SECTIONS: &[&str] = &["Goals", "Return Format", "Warnings"];
+
+/// Parse hints text. A section starts with a `[Goals]`, `[Return Format]` or
+/// `[Warnings]` header line and runs until the next header; text may also follow
+/// the header on the same line. Text without any headers is kept verbatim as notes.
+pub fn parse_hints(text: &str) -> SessionHints {
+    let mut hints = SessionHints::default();
+    let mut notes = Vec::new();
+    let mut section: Option<(&str, Vec<&str>)> = None;
+    let mut structured = false;
+
+    for line in text.lines() {
+        let header = line.trim_start()
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .and_then(|(name, inline)| {
+                HINT_SECTIONS.iter()
+                    .find(|section| section.eq_ignore_ascii_case(name.trim()))
+                    .map(|section| (*section, inline.trim()))
+            });
+        match header {
+            Some((name, inline)) => {
+                structured = true;
+                if let Some((previous, lines)) = section.take() {
+                    hints.set_section(previous, &lines);
+                }
+                section = Some((name, if inline.is_empty() { Vec::new() } else { vec![inline] }));
             }
+            None => match &mut section {
+                Some((_, lines)) => lines.push(line),
+                None => notes.push(line),
+            },
+        }
+    }
+    if let Some((name, lines)) = section {
+        hints.set_section(name, &lines);
+    }
+
+    hints.notes = if structured { notes.join("\n").trim().to_string() } else { text.to_string() };
+    hints
+}
+
+impl SessionHints {
+    // Store a section's text, ignoring empty sections
+    fn set_section(&mut self, name: &str, lines: &[&str]) {
+        let value = lines.join("\n").trim().to_string();
+        if value.is_empty() {
+            return;
+        }
+        match name {
+            "Goals" => self.goals = Some(value),
+            "Return Format" => self.return_format = Some(value),
+            _ => self.warnings = Some(value),
+        }
+    }
+}
+
+/// Hints from a local `.olaHints` file, or the global `~/.ola-hints/olaHints` if there is none.
+/// An unreadable hints file counts as no hints.
+pub fn session_hints() -> SessionHints {
+    match read_hints_file() {
+        Ok(Some(text)) => parse_hints(&text),
+        _ => SessionHints::default(),
+    }
+}
+
+/// Session hints, or none at all for a `--fresh` run
+pub fn session_hints_unless(fresh: bool) -> SessionHints {
+    if fresh {
+        SessionHints::default()
+    } else {
+        session_hints()
+    }
+}
+
+// Read the local .olaHints file, falling back to the global one
+fn read_hints_file() -> std::io::Result<Option<String>> {
+    // Check local file .olaHints in the current directory
+    // The section parser is line-based, so a BOM or CRLF endings are always removed
+    if Path::new("./.olaHints").exists() {
+        return fs::read_to_string("./.olaHints").map(|text| Some(crate::project::normalize_text(&text, false)));
+    }
+    
+    // Fallback to global hints in ~/.ola-hints/olaHints
+    if let Ok(home) = std::env::var("HOME") {
+        let global_path = format!("{}/.ola-hints/olaHints", home);
+        if Path::new(&global_path).exists() {
+            return fs::read_to_string(global_path).map(|text| Some(crate::project::normalize_text(&text, false)));
         }
     }
+    Ok(None)
+}
+
+// Helper function to append the free-form part of the hints file.
+// Structured sections are applied as session defaults instead (see `session_hints`).
+fn append_hints_if_available(input_data: &mut String) -> Result<(), Box<dyn std::error::Error>> {
+    let hints = match read_hints_file()? {
+        Some(text) => parse_hints(&text),
+        None => return Ok(()),
+    };
 
     // If hints were found, append them to the input data
-    if !hints.is_empty() {
-        input_data.push_str(&format!("\nHINTS: {}", hints));
+    if !hints.notes.is_empty() {
+        input_data.push_str(&format!("\nHINTS: {}", hints.notes));
     }
     
     Ok(())
 }
 
 // Helper function to log session information
+#[allow(clippy::too_many_arguments)]
 fn log_session(
     goals: &str,
     return_type: &str,
     warnings: &str,
     model: &str,
-    response: &str
+    response: &str,
+    raw_response: Option<&str>,
+    finish_reason: Option<&str>,
+    tags: &[String],
 ) -> Result<(), Box<dyn std::error::Error>> {
     let settings = crate::settings::Settings::load().unwrap_or_default();
     
@@ -208,6 +1107,7 @@ fn log_session(
     
     // Build log entry with optional recursion information
     let mut log_entry = json!({
+        "id": uuid::Uuid::new_v4().to_string(),
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "goals": goals,
         "return_format": return_type,
@@ -221,47 +1121,195 @@ fn log_session(
         log_entry["recursion_wave"] = json!(wave);
     }
     
+    if let Some(reason) = finish_reason {
+        log_entry["finish_reason"] = json!(reason);
+    }
+    
+    if !tags.is_empty() {
+        log_entry["tags"] = json!(tags);
+    }
+    
+    // Keep the unfiltered response only when explicitly requested
+    if let Some(raw) = raw_response {
+        log_entry["raw_response"] = json!(raw);
+    }
+    
     piping::append_to_log(&settings.behavior.log_file, &log_entry.to_string())?;
     Ok(())
 }
 
-/// Interactive iterations with user feedback for LLM responses  
+/// Interactive iterations with user feedback for LLM responses. After each response the
+/// user is asked for feedback; the next iteration is sent the transcript so far, and an
+/// empty line accepts the response and stops early.
 pub fn interactive_iterations(
     goals: &str,
     return_type: &str,
     warnings: &str,
-    clipboard: bool,
     context: Option<&str>,
-    no_thinking: bool,
+    options: &PromptOptions,
     max_iterations: u8,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    run_iterations(
+        max_iterations,
+        context,
+        |iteration_context| structure_reasoning(goals, return_type, warnings, iteration_context, options),
+        read_feedback,
+    )
+}
+
+/// Interactive iterations with project context (files, goals, and contexts)
+pub fn interactive_iterations_with_project(
+    project_id: Option<&str>,
+    goals: &str,
+    return_type: &str,
+    warnings: &str,
+    options: &PromptOptions,
+    max_iterations: u8,
+) -> Result<(), Box<dyn std::error::Error>> {
+    run_iterations(
+        max_iterations,
+        None,
+        |iteration_context| structure_reasoning_with_project(project_id, goals, return_type, warnings, iteration_context, options),
+        read_feedback,
+    )
+}
+
+/// Result of re-running a prompt with `--repeat-until`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepeatOutcome {
+    /// The last response, which matched unless every attempt was used up
+    pub response: String,
+    pub attempts: u8,
+    pub matched: bool,
+}
+
+/// Run the prompt until a response matches `pattern`, at most `max_repeats` times. Each
+/// attempt after the first gets the previous response as extra context.
+pub fn repeat_until(
+    goals: &str,
+    return_type: &str,
+    warnings: &str,
+    context: Option<&str>,
+    options: &PromptOptions,
+    pattern: &str,
+    max_repeats: u8,
+) -> Result<RepeatOutcome, Box<dyn std::error::Error>> {
+    let pattern = Regex::new(pattern)?;
+    let mut response = String::new();
+    for attempt in 1..=max_repeats {
+        if !options.quiet {
+            println!();
+            output::print_banner(&format!("🔁 Attempt {}/{} 🔁", attempt, max_repeats), output::Color::BrightCyan);
+            println!();
+        }
+        let attempt_context = if attempt == 1 {
+            context.map(str::to_string)
+        } else {
+            let previous = format!("Previous response (it did not match {}):\n{}", pattern, response);
+            Some(match context {
+                Some(context) => format!("{}\n\n{}", context, previous),
+                None => previous,
+            })
+        };
+        response = structure_reasoning(goals, return_type, warnings, attempt_context.as_deref(), options)?;
+        if pattern.is_match(&response) {
+            return Ok(RepeatOutcome { response, attempts: attempt, matched: true });
+        }
+    }
+    Ok(RepeatOutcome { response, attempts: max_repeats, matched: false })
+}
+
+// Run one reasoning pass per iteration with progress banners in between. Each pass after
+// the first gets the transcript of earlier responses and feedback as extra context; a
+// `None` from `feedback` accepts the last response and ends the loop early.
+fn run_iterations<F, G>(
+    max_iterations: u8,
+    context: Option<&str>,
+    mut run_once: F,
+    mut feedback: G,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnMut(Option<&str>) -> Result<String, Box<dyn std::error::Error>>,
+    G: FnMut(u8) -> std::io::Result<Option<String>>,
+{
+    let mut transcript = String::new();
+    let mut completed = max_iterations;
     for iteration in 1..=max_iterations {
         println!();
         output::print_banner(&format!("🔄 Iteration {}/{} 🔄", iteration, max_iterations), output::Color::BrightCyan);
         println!();
         
-        // Execute the structured reasoning for this iteration
-        structure_reasoning(goals, return_type, warnings, clipboard, context, no_thinking)?;
+        // Execute the structured reasoning for this iteration with everything said so far
+        let iteration_context = if transcript.is_empty() {
+            context.map(str::to_string)
+        } else {
+            Some(match context {
+                Some(context) => format!("{}\n\n{}", context, transcript),
+                None => transcript.clone(),
+            })
+        };
+        let response = run_once(iteration_context.as_deref())?;
         
-        // For now, we'll just run the same prompt multiple times
-        // In a more advanced version, we could collect feedback between iterations
         if iteration < max_iterations {
             println!();
             output::print_success(&format!("Completed iteration {} of {}", iteration, max_iterations));
+            let Some(notes) = feedback(iteration)? else {
+                completed = iteration;
+                break;
+            };
+            transcript.push_str(&format!("Response from iteration {}:\n{}\n\n", iteration, response.trim()));
+            if !notes.trim().is_empty() {
+                transcript.push_str(&format!("Feedback on iteration {}:\n{}\n\n", iteration, notes.trim()));
+            }
             output::print_wave_animation(iteration as usize, "Preparing next iteration...");
-            std::thread::sleep(std::time::Duration::from_millis(800));
+            output::animation_pause(800);
             output::clear_line();
         }
     }
     
     println!();
-    output::print_rainbow(&format!("🎉 Completed {} iterations! 🎉", max_iterations));
+    if completed < max_iterations {
+        output::print_rainbow(&format!("🎉 Accepted the response after {} of {} iterations! 🎉", completed, max_iterations));
+    } else {
+        output::print_rainbow(&format!("🎉 Completed {} iterations! 🎉", max_iterations));
+    }
     Ok(())
 }
 
+// Ask for feedback on the last iteration; `None` means the user accepted it with an empty
+// line. Without a terminal, one line is read from stdin so feedback can be scripted, and
+// end of input moves on without feedback.
+fn read_feedback(iteration: u8) -> std::io::Result<Option<String>> {
+    let line = if atty::is(atty::Stream::Stdin) && output::stderr_is_tty() {
+        dialoguer::Input::<String>::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(format!("✏️  Feedback on iteration {} (empty to accept)", iteration))
+            .allow_empty(true)
+            .interact_text()?
+    } else {
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line)? == 0 {
+            return Ok(Some(String::new()));
+        }
+        line
+    };
+    if line.trim().is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(line.trim().to_string()))
+    }
+}
+
+// Most bytes of each project file included in a project prompt
+const PROJECT_FILE_MAX_BYTES: usize = 10000;
+
 /// Enhanced prompt building that includes project files, goals, and contexts
-pub fn build_project_prompt(project: &Project, user_prompt: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
-    let project_manager = ProjectManager::new()?;
+/// Section labels come from the prompt template in settings
+pub fn build_project_prompt(
+    project: &Project,
+    user_prompt: Option<&str>,
+    template: &crate::settings::PromptTemplate,
+    project_context: &crate::settings::ProjectContextSettings,
+) -> Result<String, Box<dyn std::error::Error>> {
     let mut prompt_parts = Vec::new();
     
     // Add goals section if any goals exist
@@ -284,18 +1332,31 @@ pub fn build_project_prompt(project: &Project, user_prompt: Option<&str>) -> Res
     
     // Add files section if any files exist
     if !project.files.is_empty() {
+        let project_manager = ProjectManager::new()?.with_keep_crlf(project_context.keep_crlf);
         prompt_parts.push("## Project Files".to_string());
         
-        for file in &project.files {
+        // Drop files by the configured policy when over the context budget
+        let evicted = crate::project::files_to_evict(&project.files, project_context.max_bytes, project_context.eviction_policy);
+        if !evicted.is_empty() {
+            let names: Vec<&str> = evicted.iter().map(|f| f.filename.as_str()).collect();
+            let note = format!(
+                "[Omitted {} file(s) over the {}-byte context budget ({}): {}]",
+                evicted.len(), project_context.max_bytes, project_context.eviction_policy, names.join(", ")
+            );
+            eprintln!("📉 {}", note);
+            prompt_parts.push(note);
+        }
+        
+        for file in project.files.iter().filter(|f| !evicted.iter().any(|e| e.id == f.id)) {
             prompt_parts.push(format!("### File: {}", file.filename));
             
             // Try to read file content as text
             match project_manager.read_file_as_text(&project.id, &file.id) {
                 Ok(Some(content)) => {
                     // Limit file content to prevent prompt from becoming too large
-                    let content = if content.len() > 10000 {
+                    let content = if content.len() > PROJECT_FILE_MAX_BYTES {
                         format!("{}...\n[Content truncated - file is {} bytes]", 
-                               &content[..10000], file.size)
+                               &content[..PROJECT_FILE_MAX_BYTES], file.size)
                     } else {
                         content
                     };
@@ -318,7 +1379,7 @@ pub fn build_project_prompt(project: &Project, user_prompt: Option<&str>) -> Res
     // Add user prompt if provided
     if let Some(user_input) = user_prompt {
         if !prompt_parts.is_empty() {
-            prompt_parts.push("## User Request".to_string());
+            prompt_parts.push(template.user_request_label.clone());
         }
         prompt_parts.push(user_input.to_string());
     }
@@ -326,20 +1387,34 @@ pub fn build_project_prompt(project: &Project, user_prompt: Option<&str>) -> Res
     Ok(prompt_parts.join("\n"))
 }
 
+// Keep only the requested project files (`project run --file`); every id must belong to the project
+fn select_project_files(mut project: Project, file_ids: &[String]) -> Result<Project, Box<dyn std::error::Error>> {
+    if file_ids.is_empty() {
+        return Ok(project);
+    }
+    if let Some(missing) = file_ids.iter().find(|id| !project.files.iter().any(|f| &f.id == *id)) {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("File ID '{}' not found in project '{}'", missing, project.name),
+        )));
+    }
+    project.files.retain(|f| file_ids.contains(&f.id));
+    Ok(project)
+}
+
 /// Enhanced structured reasoning with project support
 pub fn structure_reasoning_with_project(
     project_id: Option<&str>,
     goals: &str,
     return_type: &str,
     warnings: &str,
-    clipboard: bool,
     context: Option<&str>,
-    no_thinking: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
+    options: &PromptOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
     let project_manager = ProjectManager::new()?;
     
     // Load project or use default
-    let project = if let Some(id) = project_id {
+    let mut project = if let Some(id) = project_id {
         project_manager.load_project(id)?.ok_or_else(|| {
             std::io::Error::new(std::io::ErrorKind::NotFound, format!("Project not found: {}", id))
         })?
@@ -347,66 +1422,71 @@ pub fn structure_reasoning_with_project(
         project_manager.get_default_project()?
     };
     
-    // Build enhanced prompt with project data
-    let mut enhanced_prompt = build_project_prompt(&project, Some(goals))?;
-    
-    // Add additional context if provided
-    if let Some(ctx) = context {
-        enhanced_prompt = format!("{}\n\nAdditional Context: {}", enhanced_prompt, ctx);
-    }
-    
     // Try to load settings
-    let settings = crate::settings::Settings::load().unwrap_or_default();
+    let settings = options.settings();
     
-    // Format the prompt with enhanced content
-    let input_data = format_prompt(&enhanced_prompt, return_type, warnings, None);
+    // Only show what would be sent; a dry run leaves the project's access time alone
+    if options.dry_run {
+        let project = select_project_files(project, &options.project_files)?;
+        let final_input = build_project_run_prompt(&settings, &project, goals, return_type, warnings, context, options)?;
+        println!("{}", final_input);
+        return Ok(final_input);
+    }
+    project_manager.mark_accessed(&mut project)?;
+    let project = select_project_files(project, &options.project_files)?;
     
-    // Read and append hints if available
-    let mut final_input = input_data;
-    append_hints_if_available(&mut final_input)?;
+    // Build enhanced prompt with project data and context, then add the format and hints
+    let enhanced_prompt = project_request(&settings, &project, goals, context, options)?;
+    let final_input = complete_project_prompt(&settings, &enhanced_prompt, return_type, warnings, options)?;
+    let parts = project_prompt_parts(&project, goals, context, &options.project_context(&settings));
+    check_request_size(&final_input, &parts, &settings, options.fail_fast)?;
     
     // Load current configuration and create API client
-    let api_client = create_api_client_from_config()?;
-    
-    // Use model from config, settings, or fallback to default
     let config = crate::config::Config::load()?;
-    let provider_config = config.get_active_provider().ok_or_else(|| {
-        std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No active provider configured. Run 'ola configure' first.",
-        )
-    })?;
+    let provider_config = options.provider_config(&config)?;
+    let api_client = create_api_client_with_effort(&provider_config, options.reasoning_effort(&settings), &settings)?;
     
-    let model = provider_config
-        .model
-        .as_deref()
-        .unwrap_or(&settings.default_model);
-    output::println_colored(&format!("🧠 Using model: {} with project: {}", model, project.name), output::Color::BrightBlue);
+    // Use model from config, settings, or fallback to default
+    let model = resolve_model(&provider_config, &settings, &config)?;
+    if !options.quiet {
+        output::eprintln_colored(&format!("🧠 Using model: {} with project: {}", model, project.name), output::Color::BrightBlue);
+    }
     
-    // Stream the response
-    let response = stream_response(&api_client, &final_input, model, no_thinking)?;
+    // Stream the response, falling back to alternate models on retryable failures
+    let pretty = show_pretty_json(return_type, options);
+    let result = stream_with_fallback(&api_client, &final_input, model, options.fallback_models(&settings), options.no_thinking, options.quiet, !options.clipboard_only && !pretty && !options.json_repair, options.context_window_check(&settings))?;
+    if pretty {
+        print_json_response(&result.content);
+    }
+    let response = finalize_response(result.content, options);
     
     // Handle clipboard copy if requested
-    if clipboard {
-        match clipboard::copy_to_clipboard(&response) {
-            Ok(_) => output::print_success("Response copied to clipboard"),
-            Err(e) => output::print_error(&format!("Failed to copy to clipboard: {}", e))
-        }
+    if options.clipboard || options.clipboard_only {
+        copy_response(&clipboard_text(&response, options), options.quiet);
+    }
+    if let Some(path) = &options.output_file {
+        write_output_file(path, &response)?;
     }
     
     // Log session if enabled in settings
     if settings.behavior.enable_logging {
-        log_session(&enhanced_prompt, return_type, warnings, model, &response)?;
+        let raw_response = options.log_raw_response(&settings).then_some(result.raw.as_str());
+        log_session(&enhanced_prompt, return_type, warnings, &result.model, &response, raw_response, result.finish_reason.as_deref(), &options.tags)?;
     }
     
-    Ok(())
+    check_compliance(return_type, &response, options)?;
+    Ok(response)
 }
 
-// Test result structure
+// Response text along with the model that produced it
 #[derive(Debug)]
 pub struct PromptResult {
     pub content: String,
+    // Response as received, before thinking blocks were filtered
+    pub raw: String,
     pub model: String,
+    // Why the provider ended the response, if it said
+    pub finish_reason: Option<String>,
 }
 
 // Test version of structure_reasoning
@@ -438,9 +1518,12 @@ pub fn structure_reasoning_test(
     
     // Here's where we would mock the API call in tests
     // For testing, we'll just return the input as the response
+    let content = format!("This is a mocked response from the {} API.", provider_config.provider);
     Ok(PromptResult {
-        content: format!("This is a mocked response from the {} API.", provider_config.provider),
+        raw: content.clone(),
+        content,
         model: model.to_string(),
+        finish_reason: None,
     })
 }
 
@@ -471,9 +1554,12 @@ pub fn stream_non_think_test(
     
     // Here's where we would mock the API call in tests
     // For testing, we'll just return the input as the response
+    let content = format!("This is a mocked response from the {} API.", provider_config.provider);
     Ok(PromptResult {
-        content: format!("This is a mocked response from the {} API.", provider_config.provider),
+        raw: content.clone(),
+        content,
         model: model.to_string(),
+        finish_reason: None,
     })
 }
 
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Application settings structure for customizing ola behavior
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -10,6 +10,10 @@ pub struct Settings {
     #[serde(default = "default_model")]
     pub default_model: String,
     
+    /// Models to try, in order, when the primary model fails with a retryable error
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
+    
     /// Default prompt template customization
     #[serde(default)]
     pub prompt_template: PromptTemplate,
@@ -21,22 +25,55 @@ pub struct Settings {
     /// Behavior customization settings
     #[serde(default)]
     pub behavior: BehaviorSettings,
+    
+    /// Provider and model asked to explain failures when --explain-errors is set
+    #[serde(default)]
+    pub error_helper: ErrorHelperSettings,
+    
+    /// Limits on how much project file content goes into a prompt
+    #[serde(default)]
+    pub project_context: ProjectContextSettings,
+    
+    /// Which provider wins when no provider is configured and several API key
+    /// environment variables are set; unlisted providers are tried afterwards
+    #[serde(default = "default_env_provider_priority")]
+    pub env_provider_priority: Vec<String>,    
+    /// Regexes whose matches are replaced with [REDACTED] before a prompt is sent,
+    /// on top of the built-in patterns for common API key and token shapes
+    #[serde(default)]
+    pub redact_patterns: Vec<String>,
 }
 
 /// Settings for the prompt template
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PromptTemplate {
-    /// Text to display before the goals section
-    #[serde(default = "default_goals_prefix")]
+    /// Text to display before the goals section (none unless set)
+    #[serde(default)]
     pub goals_prefix: String,
     
-    /// Text to display before the return format section
-    #[serde(default = "default_return_format_prefix")]
+    /// Text to display before the return format section (none unless set)
+    #[serde(default)]
     pub return_format_prefix: String,
     
-    /// Text to display before the warnings section
-    #[serde(default = "default_warnings_prefix")]
+    /// Text to display before the warnings section (none unless set)
+    #[serde(default)]
     pub warnings_prefix: String,
+    
+    /// Heading placed above context appended to a prompt (e.g. piped input)
+    #[serde(default = "default_context_label")]
+    pub context_label: String,
+    
+    /// Line placed before and after a context block; empty for none
+    #[serde(default = "default_context_separator")]
+    pub context_separator: String,
+    
+    /// Heading placed above extra context appended to a project prompt
+    #[serde(default = "default_additional_context_label")]
+    pub additional_context_label: String,
+    
+    /// Heading placed before the user's request in a project prompt
+    #[serde(default = "default_user_request_label")]
+    pub user_request_label: String,
 }
 
 /// Default settings for command flags
@@ -46,6 +83,18 @@ pub struct DefaultSettings {
     #[serde(default = "default_return_format")]
     pub return_format: String,
     
+    /// Default warnings when not specified
+    #[serde(default)]
+    pub warnings: String,
+    
+    /// Language responses should be written in (code like `fr` or a name); empty for no instruction
+    #[serde(default)]
+    pub response_language: String,
+    
+    /// Reasoning effort (low, medium or high) for models that accept one; empty to leave it unset
+    #[serde(default)]
+    pub reasoning_effort: String,
+    
     /// Default to quiet mode
     #[serde(default)]
     pub quiet: bool,
@@ -57,6 +106,19 @@ pub struct DefaultSettings {
     /// Default to copying results to clipboard
     #[serde(default)]
     pub clipboard: bool,
+    
+    /// Collapse whitespace and newlines in piped input to single spaces
+    #[serde(default)]
+    pub collapse_whitespace: bool,
+    
+    /// Sampling temperature for every provider; unset uses the model's recommended default.
+    /// Models that don't accept a temperature (e.g. OpenAI o-series) never get one
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    
+    /// Output token limit for every provider; unset uses the model's recommended default
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
 }
 
 /// Behavior customization settings
@@ -70,9 +132,159 @@ pub struct BehaviorSettings {
     #[serde(default = "default_enable_logging")]
     pub enable_logging: bool,
     
+    /// Also log the unfiltered response, including thinking blocks hidden by --no-thinking.
+    /// Off by default because the log then holds full responses.
+    #[serde(default)]
+    pub log_raw_response: bool,
+    
+    /// Stop recursion early once a wave's response is at least this similar (0.0-1.0)
+    /// to the previous wave's; 0 disables the check
+    #[serde(default)]
+    pub convergence_threshold: f64,
+    
     /// Thinking animation customization
     #[serde(default)]
     pub thinking_animation: ThinkingAnimation,
+    
+    /// Color theme for terminal output
+    #[serde(default)]
+    pub theme: Theme,
+    
+    /// Show emoji in output; unset detects support from TERM and the locale,
+    /// false replaces them with ASCII markers such as [OK] and [x]
+    #[serde(default)]
+    pub emoji: Option<bool>,
+    
+    /// What to do when a prompt's estimated tokens exceed the model's known context window
+    #[serde(default)]
+    pub context_window_check: ContextWindowCheck,
+    
+    /// Most bytes of piped input to read; anything past it is ignored with a warning.
+    /// 0 uses the built-in 64 MiB cap
+    #[serde(default)]
+    pub max_stdin_bytes: u64,
+    
+    /// Seconds a helper command (`--context-cmd`) may run before it is killed; 0 uses 30
+    #[serde(default)]
+    pub command_timeout_secs: u64,
+    
+    /// Most bytes of helper command output to keep, truncating the rest with a warning.
+    /// 0 uses `project_context.max_bytes`, or 64 KiB when that is unset too
+    #[serde(default)]
+    pub command_max_output_bytes: u64,
+    
+    /// Largest request body to send without a warning (or at all, with --fail-fast).
+    /// 0 uses the built-in 10 MiB limit
+    #[serde(default)]
+    pub max_request_bytes: u64,
+    
+    /// Seconds a provider request may take before it is abandoned; 0 waits indefinitely
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    
+    /// Neovim used by `ola settings --edit` instead of `$VISUAL`/`$EDITOR` when enabled
+    #[serde(default)]
+    pub nvim: NvimSettings,
+}
+
+/// Neovim editor settings
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NvimSettings {
+    /// Open files in Neovim rather than the editor from the environment
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Neovim executable
+    #[serde(default = "default_nvim_path")]
+    pub path: String,
+    
+    /// Extra arguments passed before the file name
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Handling of prompts that are too long for the model's context window
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextWindowCheck {
+    /// Print a warning and send anyway
+    #[default]
+    Warn,
+    /// Print the warning and don't send the prompt
+    Refuse,
+    /// Skip the check
+    Off,
+}
+
+/// Palette used by the output helpers
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Full ocean and rainbow colors
+    #[default]
+    Ocean,
+    /// No colors at all
+    Mono,
+    /// Plain red/green/yellow for errors, successes and warnings, and banners without emoji
+    Minimal,
+}
+
+/// Settings for the provider that explains API errors
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ErrorHelperSettings {
+    /// Configured provider to ask (e.g. "Ollama"); empty disables explanations
+    #[serde(default)]
+    pub provider: String,
+    
+    /// Model to use; empty uses the provider's configured or default model
+    #[serde(default)]
+    pub model: String,
+}
+
+/// Settings for project file storage and the context budget
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ProjectContextSettings {
+    /// Total bytes of project files to include; 0 means no limit
+    #[serde(default)]
+    pub max_bytes: u64,
+    
+    /// Which files to drop first when `max_bytes` is exceeded
+    #[serde(default)]
+    pub eviction_policy: EvictionPolicy,
+    
+    /// Store newly uploaded project files gzip-compressed
+    #[serde(default)]
+    pub compress_files: bool,
+    
+    /// Send project files with their CRLF line endings instead of converting them to LF
+    #[serde(default)]
+    pub keep_crlf: bool,
+}
+
+/// Order in which project files are dropped when over the context budget
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Drop the earliest uploaded files first
+    #[default]
+    #[serde(rename = "oldest_first")]
+    Oldest,
+    /// Drop the biggest files first
+    #[serde(rename = "largest_first")]
+    Largest,
+    /// Drop the most recently uploaded files first
+    #[serde(rename = "newest_first")]
+    Newest,
+}
+
+impl std::fmt::Display for EvictionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            EvictionPolicy::Oldest => "oldest_first",
+            EvictionPolicy::Largest => "largest_first",
+            EvictionPolicy::Newest => "newest_first",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 /// Settings for thinking animation
@@ -92,16 +304,24 @@ fn default_model() -> String {
     "gpt-5".to_string()
 }
 
-fn default_goals_prefix() -> String {
-    "🏆 Goals: ".to_string()
+fn default_env_provider_priority() -> Vec<String> {
+    crate::config::SUPPORTED_PROVIDERS.iter().map(|info| info.name.to_string()).collect()
+}
+
+fn default_context_label() -> String {
+    "Context:".to_string()
+}
+
+fn default_context_separator() -> String {
+    "---".to_string()
 }
 
-fn default_return_format_prefix() -> String {
-    "📝 Return Format: ".to_string()
+fn default_additional_context_label() -> String {
+    "Additional Context:".to_string()
 }
 
-fn default_warnings_prefix() -> String {
-    "⚠️ Warnings: ".to_string()
+fn default_user_request_label() -> String {
+    "## User Request".to_string()
 }
 
 fn default_return_format() -> String {
@@ -124,6 +344,38 @@ fn default_thinking_text() -> String {
     "thinking...".to_string()
 }
 
+fn default_request_timeout_secs() -> u64 {
+    120
+}
+
+fn default_nvim_path() -> String {
+    "nvim".to_string()
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self {
+            goals_prefix: String::new(),
+            return_format_prefix: String::new(),
+            warnings_prefix: String::new(),
+            context_label: default_context_label(),
+            context_separator: default_context_separator(),
+            additional_context_label: default_additional_context_label(),
+            user_request_label: default_user_request_label(),
+        }
+    }
+}
+
+impl Default for NvimSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_nvim_path(),
+            args: Vec::new(),
+        }
+    }
+}
+
 impl Default for ThinkingAnimation {
     fn default() -> Self {
         Self {
@@ -134,6 +386,12 @@ impl Default for ThinkingAnimation {
 }
 
 impl Settings {
+    /// Load the settings file if there is a valid one, without creating it
+    pub fn load_existing() -> Option<Self> {
+        let content = fs::read_to_string(get_settings_path().ok()?).ok()?;
+        serde_yaml::from_str(&content).ok()
+    }
+
     /// Load settings from file, or create default settings if the file doesn't exist
     pub fn load() -> Result<Self, io::Error> {
         let settings_path = get_settings_path()?;
@@ -145,7 +403,128 @@ impl Settings {
 
         let settings_str = fs::read_to_string(&settings_path)?;
         let settings = serde_yaml::from_str(&settings_str)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            .map_err(|e| invalid_yaml_file(&settings_path, &settings_str, &e))?;
+        Ok(settings)
+    }
+
+    /// Check that the settings file and any legacy `config.json` parse, without creating
+    /// either. The error names the file and the position of the problem.
+    pub fn validate_files() -> Result<(), io::Error> {
+        let dir = crate::profile::ola_dir()?;
+        for path in [dir.join("settings.yaml"), dir.join("config.json")] {
+            if let Ok(content) = fs::read_to_string(&path) {
+                check_file(&path, &content)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move each settings or provider file that doesn't parse to `<file>.bak` and recreate
+    /// the default settings. Returns the backups made; files that parse are left alone.
+    pub fn repair() -> Result<Vec<PathBuf>, io::Error> {
+        let dir = crate::profile::ola_dir()?;
+        let mut backups = Vec::new();
+        for path in [dir.join("settings.yaml"), dir.join("config.json")] {
+            let Ok(content) = fs::read_to_string(&path) else { continue };
+            if check_file(&path, &content).is_err() {
+                let backup = PathBuf::from(format!("{}.bak", path.display()));
+                fs::rename(&path, &backup)?;
+                backups.push(backup);
+            }
+        }
+        if !backups.is_empty() && !get_settings_path()?.exists() {
+            Settings::default().save()?;
+        }
+        Ok(backups)
+    }
+
+    /// Open the settings file in an editor and validate the result.
+    /// If the edited file no longer parses, the previous contents are restored.
+    /// A file that doesn't parse can still be opened, so it can be fixed.
+    pub fn edit_with<F>(edit: F) -> Result<Self, io::Error>
+    where
+        F: FnOnce(&std::path::Path) -> io::Result<()>,
+    {
+        // Make sure there is a file to edit
+        let settings_path = get_settings_path()?;
+        if !settings_path.exists() {
+            Settings::default().save()?;
+        }
+        Settings::edit_file_with(&settings_path, edit)
+    }
+
+    /// Edit a specific settings file, keeping a backup in `<file>.edit.bak` until the new
+    /// contents validate
+    pub fn edit_file_with<F>(path: &std::path::Path, edit: F) -> Result<Self, io::Error>
+    where
+        F: FnOnce(&std::path::Path) -> io::Result<()>,
+    {
+        let backup_path = PathBuf::from(format!("{}.edit.bak", path.display()));
+        fs::copy(path, &backup_path)?;
+
+        let result = edit(path)
+            .and_then(|_| fs::read_to_string(path))
+            .and_then(|content| {
+                serde_yaml::from_str::<Settings>(&content)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, describe_yaml_error(&content, &e)))
+            });
+
+        match result {
+            Ok(settings) => {
+                fs::remove_file(&backup_path)?;
+                Ok(settings)
+            }
+            Err(e) => {
+                fs::rename(&backup_path, path)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the current settings and provider configuration to `path` as one YAML file.
+    /// Provider API keys are left blank unless `include_secrets` is set.
+    pub fn export_to(path: &Path, include_secrets: bool) -> Result<(), io::Error> {
+        let settings = Settings::load()?;
+        let mut config = crate::config::Config::load()?;
+        if !include_secrets {
+            for provider in &mut config.providers {
+                provider.api_key.clear();
+            }
+        }
+
+        fs::write(path, combined_yaml(&settings, &config)?)?;
+        if include_secrets {
+            restrict_permissions(path)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the settings file with the validated contents of `path`.
+    /// The current file is kept as `settings.yaml.import.bak`, and providers the imported file
+    /// leaves without an API key keep the key currently configured for them.
+    pub fn import_from(path: &Path) -> Result<Self, io::Error> {
+        let content = fs::read_to_string(path)?;
+        let invalid = |e: serde_yaml::Error| io::Error::new(io::ErrorKind::InvalidData, describe_yaml_error(&content, &e));
+        let settings: Settings = serde_yaml::from_str(&content).map_err(invalid)?;
+        let mut config: crate::config::Config = serde_yaml::from_str(&content).map_err(invalid)?;
+
+        let current = crate::config::Config::load()?;
+        for provider in config.providers.iter_mut().filter(|p| p.api_key.is_empty()) {
+            if let Some(existing) = current.providers.iter().find(|p| p.provider == provider.provider) {
+                provider.api_key = existing.api_key.clone();
+            }
+        }
+
+        let settings_path = get_settings_path()?;
+        let settings_dir = settings_path.parent().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{} has no parent directory", settings_path.display()))
+        })?;
+        fs::create_dir_all(settings_dir)?;
+        if settings_path.exists() {
+            fs::copy(&settings_path, settings_path.with_extension("yaml.import.bak"))?;
+        }
+        fs::write(&settings_path, combined_yaml(&settings, &config)?)?;
+        restrict_permissions(&settings_path)?;
         Ok(settings)
     }
 
@@ -155,9 +534,10 @@ impl Settings {
         let settings_dir = settings_path.parent().unwrap();
         fs::create_dir_all(settings_dir)?;
 
-        let settings_str = serde_yaml::to_string(self)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // Provider configuration shares the file, so its keys are kept
+        let settings_str = merged_yaml(&settings_path, self)?;
         fs::write(&settings_path, settings_str)?;
+        restrict_permissions(&settings_path)?;
 
         Ok(())
     }
@@ -167,17 +547,104 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             default_model: default_model(),
+            fallback_models: Vec::new(),
             prompt_template: PromptTemplate::default(),
             defaults: DefaultSettings::default(),
-            behavior: BehaviorSettings::default(),
+            behavior: BehaviorSettings {
+                request_timeout_secs: default_request_timeout_secs(),
+                ..BehaviorSettings::default()
+            },
+            error_helper: ErrorHelperSettings::default(),
+            project_context: ProjectContextSettings::default(),
+            env_provider_priority: default_env_provider_priority(),
+            redact_patterns: Vec::new(),
         }
     }
 }
 
+// Serialize settings and provider configuration into the shared settings file layout
+fn combined_yaml(settings: &Settings, config: &crate::config::Config) -> Result<String, io::Error> {
+    let to_value = |value: serde_yaml::Result<serde_yaml::Value>| {
+        value.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    };
+    let mut document = to_value(serde_yaml::to_value(config))?;
+    if let (Some(document), serde_yaml::Value::Mapping(settings)) = (document.as_mapping_mut(), to_value(serde_yaml::to_value(settings))?) {
+        document.extend(settings);
+    }
+    serde_yaml::to_string(&document).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Serialize `value` over the YAML file at `path`: its top-level keys replace the file's,
+/// and any other keys in the file are kept. A file that doesn't parse is an error rather
+/// than being written over.
+pub(crate) fn merged_yaml<T: Serialize>(path: &Path, value: &T) -> Result<String, io::Error> {
+    let invalid = |e: serde_yaml::Error| io::Error::new(io::ErrorKind::InvalidData, e);
+    let mut document: serde_yaml::Mapping = match fs::read_to_string(path) {
+        Ok(existing) if !existing.trim().is_empty() => serde_yaml::from_str(&existing)
+            .map_err(|e| invalid_yaml_file(path, &existing, &e))?,
+        _ => serde_yaml::Mapping::new(),
+    };
+    if let serde_yaml::Value::Mapping(keys) = serde_yaml::to_value(value).map_err(invalid)? {
+        document.extend(keys);
+    }
+    serde_yaml::to_string(&document).map_err(invalid)
+}
+
+// Limit a file holding API keys to its owner (600)
+fn restrict_permissions(path: &Path) -> Result<(), io::Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+// Format a YAML parse error with the offending line for context
+/// Error for a YAML file that doesn't parse, naming the file and showing the offending line
+pub(crate) fn invalid_yaml_file(path: &Path, content: &str, error: &serde_yaml::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} is not valid YAML: {}", path.display(), describe_yaml_error(content, error)),
+    )
+}
+
+/// Error for a JSON file that doesn't parse, naming the file and the position
+pub(crate) fn invalid_json_file(path: &Path, error: &serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{} is not valid JSON: {}", path.display(), error))
+}
+
+// Parse a settings or legacy provider file the way loading it would
+fn check_file(path: &Path, content: &str) -> Result<(), io::Error> {
+    if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        serde_json::from_str::<crate::config::Config>(content).map_err(|e| invalid_json_file(path, &e))?;
+    } else {
+        serde_yaml::from_str::<Settings>(content).map_err(|e| invalid_yaml_file(path, content, &e))?;
+        serde_yaml::from_str::<crate::config::Config>(content).map_err(|e| invalid_yaml_file(path, content, &e))?;
+    }
+    Ok(())
+}
+
+fn describe_yaml_error(content: &str, error: &serde_yaml::Error) -> String {
+    match error.location() {
+        Some(location) => {
+            let line = content.lines().nth(location.line().saturating_sub(1)).unwrap_or("");
+            format!(
+                "{}\n  {} | {}\n  {} | {}^",
+                error,
+                location.line(),
+                line,
+                " ".repeat(location.line().to_string().len()),
+                " ".repeat(location.column().saturating_sub(1))
+            )
+        }
+        None => error.to_string(),
+    }
+}
+
 /// Get the path to the settings file
 fn get_settings_path() -> Result<PathBuf, io::Error> {
-    let home = std::env::var("HOME")
-        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "HOME directory not found"))?;
-    
-    Ok(PathBuf::from(home).join(".ola").join("settings.yaml"))
+    Ok(crate::profile::ola_dir()?.join("settings.yaml"))
 }
\ No newline at end of file
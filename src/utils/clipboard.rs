@@ -1,4 +1,4 @@
-// Clipboard utility for copying text to the system clipboard
+// Clipboard utility for copying text to and reading text from the system clipboard
 use std::io::Write;
 use std::process::{Command, Stdio};
 
@@ -38,3 +38,41 @@ pub fn copy_to_clipboard(text: &str) -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
+
+/// Read text from the system clipboard, trying each clipboard tool for the current OS in turn
+pub fn read_from_clipboard() -> Result<String, Box<dyn std::error::Error>> {
+    let os = std::env::consts::OS;
+    
+    let candidates: Vec<(&str, Vec<&str>)> = match os {
+        "macos" => vec![("pbpaste", vec![])],
+        "linux" => {
+            let wayland = ("wl-paste", vec!["--no-newline"]);
+            let x11 = ("xclip", vec!["-selection", "clipboard", "-o"]);
+            // Prefer the tool for the running display server
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                vec![wayland, x11]
+            } else {
+                vec![x11, wayland]
+            }
+        }
+        "windows" => vec![("powershell", vec!["-NoProfile", "-Command", "Get-Clipboard"])],
+        _ => {
+            return Err(format!("Clipboard functionality not supported on this platform: {}", os).into());
+        }
+    };
+    
+    for (cmd, args) in &candidates {
+        let output = match Command::new(cmd).args(args).stderr(Stdio::null()).output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if !output.status.success() {
+            return Err(format!("{} failed with exit code: {:?}", cmd, output.status.code()).into());
+        }
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+    
+    let tools: Vec<&str> = candidates.iter().map(|(cmd, _)| *cmd).collect();
+    Err(format!("No clipboard tool found; install one of: {}", tools.join(", ")).into())
+}
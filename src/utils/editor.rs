@@ -0,0 +1,57 @@
+// Utilities for opening content in the user's editor
+use crate::settings::NvimSettings;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+
+/// Editor to launch and its arguments: `$EDITOR`, then `$VISUAL`, then Neovim when
+/// `behavior.nvim` is enabled, then vi. An environment value may include arguments
+/// (e.g. `code --wait`).
+pub fn editor_command(nvim: &NvimSettings) -> (String, Vec<String>) {
+    let from_env = ["EDITOR", "VISUAL"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty());
+    if let Some(value) = from_env {
+        let mut parts = value.split_whitespace().map(str::to_string);
+        let program = parts.next().unwrap_or_default();
+        return (program, parts.collect());
+    }
+    if nvim.enabled {
+        return (nvim.path.clone(), nvim.args.clone());
+    }
+    ("vi".to_string(), Vec::new())
+}
+
+/// Open `content` in the given editor, wait for it to exit, and return the saved text.
+/// The content is edited in a private temporary file that is removed afterwards.
+#[allow(dead_code)] // Kept for library users; the binary edits files in place via `open_with`
+pub fn open_in_editor(content: &str, editor_cmd: &str, args: &[String]) -> io::Result<String> {
+    let path = std::env::temp_dir().join(format!("ola-edit-{}.txt", std::process::id()));
+    let mut open_options = OpenOptions::new();
+    open_options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        open_options.mode(0o600);
+    }
+    let written = open_options.open(&path).and_then(|mut file| file.write_all(content.as_bytes()));
+    let edited = written
+        .and_then(|_| open_with(editor_cmd, args, &path))
+        .and_then(|_| fs::read_to_string(&path));
+    let _ = fs::remove_file(&path);
+    edited
+}
+
+/// Open a file with the given editor program and arguments, and wait for it to exit
+pub fn open_with<S: AsRef<std::ffi::OsStr>>(program: &str, args: &[S], path: &Path) -> io::Result<()> {
+    if program.trim().is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "No editor configured"));
+    }
+    let status = Command::new(program).args(args).arg(path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("Editor '{}' exited with status: {}", program, status)));
+    }
+    Ok(())
+}
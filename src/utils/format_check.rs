@@ -0,0 +1,97 @@
+// Checks that a response follows the requested return format
+use regex::Regex;
+use serde_json::Value;
+
+/// Check a response against the return format it was asked for.
+/// `json` requires the whole response to parse as JSON; a format that is itself a JSON
+/// object or array is treated as a shape the response must match; `markdown` only needs
+/// some markdown structure. Returns `None` for formats that can't be checked, otherwise
+/// `Ok(())` or a description of what didn't comply.
+pub fn check_format(format: &str, response: &str) -> Option<Result<(), String>> {
+    // Reasoning models may still emit thinking blocks ahead of the answer
+    let think_re = Regex::new(r"(?s)<think>.*?</think>").ok()?;
+    let response = think_re.replace_all(response, "");
+    let response = response.trim();
+    let format = format.trim();
+
+    if format.eq_ignore_ascii_case("json") {
+        return Some(parse_json(strip_code_fence(response)).map(|_| ()));
+    }
+    if format.eq_ignore_ascii_case("markdown") || format.eq_ignore_ascii_case("md") {
+        return Some(check_markdown(response));
+    }
+    match serde_json::from_str::<Value>(format) {
+        Ok(shape @ (Value::Object(_) | Value::Array(_))) => {
+            Some(parse_json(strip_code_fence(response)).and_then(|value| match_shape(&shape, &value, "response")))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a return format asks for JSON: `json` itself or a JSON object/array shape
+pub fn is_json_format(format: &str) -> bool {
+    let format = format.trim();
+    format.eq_ignore_ascii_case("json")
+        || matches!(serde_json::from_str::<Value>(format), Ok(Value::Object(_) | Value::Array(_)))
+}
+
+// Models often wrap JSON in a ```json fence; the fence itself isn't part of the answer
+fn strip_code_fence(response: &str) -> &str {
+    let fenced = response.strip_prefix("```").and_then(|rest| rest.strip_suffix("```"));
+    match fenced.and_then(|body| body.split_once('\n')) {
+        Some((_language, body)) => body.trim(),
+        None => response,
+    }
+}
+
+fn parse_json(response: &str) -> Result<Value, String> {
+    serde_json::from_str(response).map_err(|e| format!("response is not valid JSON ({})", e))
+}
+
+// Every key of a shape object must be present with a value of the same kind; arrays
+// are checked element by element against the shape's first element. Null in the
+// shape accepts anything.
+fn match_shape(shape: &Value, value: &Value, path: &str) -> Result<(), String> {
+    match (shape, value) {
+        (Value::Null, _) => Ok(()),
+        (Value::Object(fields), Value::Object(object)) => {
+            for (key, field_shape) in fields {
+                let field_path = format!("{}.{}", path, key);
+                match object.get(key) {
+                    Some(field) => match_shape(field_shape, field, &field_path)?,
+                    None => return Err(format!("{} is missing", field_path)),
+                }
+            }
+            Ok(())
+        }
+        (Value::Array(items_shape), Value::Array(items)) => match items_shape.first() {
+            Some(item_shape) => items.iter().enumerate()
+                .try_for_each(|(i, item)| match_shape(item_shape, item, &format!("{}[{}]", path, i))),
+            None => Ok(()),
+        },
+        (Value::Bool(_), Value::Bool(_)) | (Value::Number(_), Value::Number(_)) | (Value::String(_), Value::String(_)) => Ok(()),
+        _ => Err(format!("{} should be {}, found {}", path, kind(shape), kind(value))),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+// Light heuristic: headings, lists, code fences, links, emphasis or tables
+fn check_markdown(response: &str) -> Result<(), String> {
+    let markdown_re = Regex::new(r"(?m)^\s{0,3}(#{1,6}\s|[-*+]\s|\d+[.)]\s|```|>\s|\|.*\|)|\[[^\]]+\]\([^)]+\)|\*\*[^*]+\*\*|`[^`]+`")
+        .map_err(|e| e.to_string())?;
+    if markdown_re.is_match(response) {
+        Ok(())
+    } else {
+        Err("response has no markdown structure (headings, lists, code, links or emphasis)".to_string())
+    }
+}
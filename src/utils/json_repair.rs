@@ -0,0 +1,79 @@
+// Utilities for recovering JSON from slightly-invalid model output
+use regex::Regex;
+
+/// Extract the first JSON object or array from a model response and repair common defects:
+/// surrounding prose, markdown fences, thinking blocks, trailing commas, and missing closers.
+/// Returns the parsed value, or an error describing why no valid JSON could be recovered.
+pub fn repair_json(response: &str) -> Result<serde_json::Value, String> {
+    // Thinking blocks may contain braces of their own, so drop them first
+    let think_re = Regex::new(r"(?s)<think>.*?</think>").map_err(|e| e.to_string())?;
+    let text = think_re.replace_all(response, "");
+
+    let start = text
+        .find(['{', '['])
+        .ok_or_else(|| "No JSON object or array found in response".to_string())?;
+
+    let mut repaired = String::new();
+    let mut closers: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in text[start..].chars() {
+        if in_string {
+            repaired.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                repaired.push(c);
+            }
+            '{' => {
+                closers.push('}');
+                repaired.push(c);
+            }
+            '[' => {
+                closers.push(']');
+                repaired.push(c);
+            }
+            '}' | ']' => {
+                strip_trailing_comma(&mut repaired);
+                closers.pop();
+                repaired.push(c);
+                if closers.is_empty() {
+                    break;
+                }
+            }
+            _ => repaired.push(c),
+        }
+    }
+
+    // Close an unterminated string and any structures left open by a truncated response
+    if in_string {
+        repaired.push('"');
+    }
+    while let Some(closer) = closers.pop() {
+        strip_trailing_comma(&mut repaired);
+        repaired.push(closer);
+    }
+
+    serde_json::from_str(&repaired).map_err(|e| format!("Repaired JSON is still invalid: {}", e))
+}
+
+// Remove a trailing comma (and the whitespace after it) before a closing bracket
+fn strip_trailing_comma(buffer: &mut String) {
+    let trimmed_len = buffer.trim_end().len();
+    if buffer[..trimmed_len].ends_with(',') {
+        buffer.truncate(trimmed_len - 1);
+    } else {
+        buffer.truncate(trimmed_len);
+    }
+}
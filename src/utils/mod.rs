@@ -1,6 +1,10 @@
 // Module exports for utility functions
 pub mod clipboard;
+pub mod editor;
+pub mod format_check;
+pub mod json_repair;
 pub mod output;
 pub mod piping;
+pub mod redact;
 
 // Re-export frequently used utility functions
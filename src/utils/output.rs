@@ -1,6 +1,95 @@
 // Output formatting utilities
+use crate::settings::Theme;
+use std::borrow::Cow;
+use std::env;
 use std::io;
 use std::io::Write;
+use std::sync::OnceLock;
+
+// Theme chosen at startup; output before `set_theme` uses the default
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Choose the palette for all output helpers. Only the first call takes effect.
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+fn theme() -> Theme {
+    THEME.get().copied().unwrap_or_default()
+}
+
+// Whether emoji are printed; output before `set_emoji` keeps them
+static EMOJI: OnceLock<bool> = OnceLock::new();
+
+/// Choose whether output helpers print emoji or ASCII markers. Only the first call takes effect.
+pub fn set_emoji(enabled: bool) {
+    let _ = EMOJI.set(enabled);
+}
+
+fn emoji_enabled() -> bool {
+    EMOJI.get().copied().unwrap_or(true)
+}
+
+/// Guess whether the terminal can render emoji: the Linux console and dumb terminals
+/// cannot, and neither can a locale that is set but not UTF-8. An unset locale is
+/// assumed to be fine.
+pub fn terminal_supports_emoji() -> bool {
+    if let Ok(term) = env::var("TERM") {
+        if term == "linux" || term == "dumb" {
+            return false;
+        }
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty());
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None => true,
+    }
+}
+
+// ASCII stand-ins for the emoji used as status prefixes
+const ASCII_MARKERS: &[(&str, &str)] = &[
+    ("✅", "[OK]"),
+    ("✓", "[OK]"),
+    ("⚠️", "[!]"),
+    ("⚠", "[!]"),
+    ("❌", "[x]"),
+    ("✗", "[x]"),
+];
+
+// Text as it should be printed: unchanged when emoji are enabled, otherwise with
+// status emoji replaced by ASCII markers and any other emoji dropped
+fn with_emoji_fallback(text: &str) -> Cow<'_, str> {
+    if emoji_enabled() || !text.chars().any(is_emoji) {
+        return Cow::Borrowed(text);
+    }
+    let mut replaced = text.to_string();
+    for (emoji, marker) in ASCII_MARKERS {
+        replaced = replaced.replace(emoji, marker);
+    }
+    let mut plain = String::with_capacity(replaced.len());
+    let mut chars = replaced.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !is_emoji(c) {
+            plain.push(c);
+            continue;
+        }
+        // Drop the space that separated a removed emoji from the text after it
+        if (plain.is_empty() || plain.ends_with(' ')) && chars.peek() == Some(&' ') {
+            chars.next();
+        }
+    }
+    Cow::Owned(plain)
+}
+
+// Emoji, plus the joiners and variation selectors that combine them
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF | 0xFE0F | 0x200D)
+}
 
 /// Enum for defining ANSI color codes
 pub enum Color {
@@ -31,7 +120,27 @@ pub enum Color {
 }
 
 impl Color {
+    /// Escape code for this color in the current theme
     pub fn code(&self) -> &str {
+        match theme() {
+            Theme::Ocean => self.ocean_code(),
+            Theme::Mono => "",
+            Theme::Minimal => self.minimal_code(),
+        }
+    }
+
+    // Errors, successes and warnings keep a basic color; everything else is plain
+    fn minimal_code(&self) -> &str {
+        match self {
+            Color::Red | Color::BrightRed => "\x1b[31m",
+            Color::Green | Color::BrightGreen => "\x1b[32m",
+            Color::Yellow | Color::BrightYellow | Color::Orange => "\x1b[33m",
+            Color::Reset => "\x1b[0m",
+            _ => "",
+        }
+    }
+
+    fn ocean_code(&self) -> &str {
         match self {
             Color::Red => "\x1b[31m",
             Color::Green => "\x1b[32m",
@@ -64,26 +173,37 @@ impl Color {
 
 /// Print an error message in red
 pub fn print_error(message: &str) {
-    eprintln!("{}Error: {}{}", Color::Red.code(), message, Color::Reset.code());
+    eprintln!("{}Error: {}{}", Color::Red.code(), with_emoji_fallback(message), Color::Reset.code());
 }
 
 /// Print a success message in green
 pub fn print_success(message: &str) {
-    eprintln!("{}✓ {}{}", Color::Green.code(), message, Color::Reset.code());
+    let marker = if emoji_enabled() { "✓" } else { "[OK]" };
+    eprintln!("{}{} {}{}", Color::Green.code(), marker, with_emoji_fallback(message), Color::Reset.code());
 }
 
 /// Print colored text to stdout
 pub fn print_colored(text: &str, color: Color) {
-    print!("{}{}{}", color.code(), text, Color::Reset.code());
+    print!("{}{}{}", color.code(), with_emoji_fallback(text), Color::Reset.code());
 }
 
 /// Print colored text with newline to stdout
 pub fn println_colored(text: &str, color: Color) {
-    println!("{}{}{}", color.code(), text, Color::Reset.code());
+    println!("{}{}{}", color.code(), with_emoji_fallback(text), Color::Reset.code());
+}
+
+/// Print colored text with newline to stderr, for status that must stay out of piped output
+pub fn eprintln_colored(text: &str, color: Color) {
+    eprintln!("{}{}{}", color.code(), with_emoji_fallback(text), Color::Reset.code());
 }
 
 /// Print a rainbow gradient text
 pub fn print_rainbow(text: &str) {
+    let text = with_emoji_fallback(text);
+    if theme() != Theme::Ocean {
+        println!("{}", text);
+        return;
+    }
     let colors = [
         Color::BrightRed,
         Color::Orange,
@@ -126,6 +246,14 @@ pub fn print_pulsing(text: &str, color: Color) {
 
 /// Print a stylized banner with borders
 pub fn print_banner(text: &str, color: Color) {
+    let stripped;
+    let text = if theme() == Theme::Minimal {
+        stripped = strip_emoji(text);
+        stripped.as_str()
+    } else {
+        stripped = with_emoji_fallback(text).into_owned();
+        stripped.as_str()
+    };
     let width = text.len() + 4;
     let border = "═".repeat(width);
     
@@ -134,40 +262,134 @@ pub fn print_banner(text: &str, color: Color) {
     println!("{}╚{}╝{}", color.code(), border, Color::Reset.code());
 }
 
+// Remove emoji (and the joiners and variation selectors that combine them) from text
+fn strip_emoji(text: &str) -> String {
+    let stripped: String = text.chars()
+        .filter(|&c| !is_emoji(c))
+        .collect();
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Whether stderr is an interactive terminal that can render in-place animations.
+/// When it is not (CI logs, redirected output), animations are skipped and status updates
+/// are printed as plain lines, whether or not --quiet is set.
+pub fn stderr_is_tty() -> bool {
+    atty::is(atty::Stream::Stderr)
+}
+
+/// Hold an animation frame on screen; returns immediately when animations are off
+pub fn animation_pause(ms: u64) {
+    if stderr_is_tty() {
+        std::thread::sleep(std::time::Duration::from_millis(ms));
+    }
+}
+
+// Print a status message as a plain append-only line for non-interactive stderr
+fn print_plain_status(message: &str) {
+    eprintln!("{}", with_emoji_fallback(message).trim());
+}
+
 /// Clear the current line
 pub fn clear_line() {
+    if !stderr_is_tty() {
+        return;
+    }
     eprint!("\r\x1B[K");
     io::stderr().flush().unwrap();
 }
 
 /// Print an animated spinner
 pub fn print_spinner_frame(frame: usize, message: &str) {
+    if !stderr_is_tty() {
+        print_plain_status(message);
+        return;
+    }
     let spinners = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
     let spinner = spinners[frame % spinners.len()];
-    eprint!("\r\x1B[K{}{} {} {}", Color::BrightCyan.code(), spinner, message, Color::Reset.code());
+    eprint!("\r\x1B[K{}{} {} {}", Color::BrightCyan.code(), spinner, with_emoji_fallback(message), Color::Reset.code());
     io::stderr().flush().unwrap();
 }
 
 /// Print wave animation
 pub fn print_wave_animation(frame: usize, text: &str) {
-    let waves = ["🌊", "🌊🌊", "🌊🌊🌊", "🌊🌊", "🌊"];
+    if !stderr_is_tty() {
+        print_plain_status(text);
+        return;
+    }
+    let waves = if emoji_enabled() {
+        ["🌊", "🌊🌊", "🌊🌊🌊", "🌊🌊", "🌊"]
+    } else {
+        ["~", "~~", "~~~", "~~", "~"]
+    };
     let wave = waves[frame % waves.len()];
-    eprint!("\r\x1B[K{}{} {}{}", Color::DeepSkyBlue.code(), wave, text, Color::Reset.code());
+    eprint!("\r\x1B[K{}{} {}{}", Color::DeepSkyBlue.code(), wave, with_emoji_fallback(text), Color::Reset.code());
+    io::stderr().flush().unwrap();
+}
+
+/// Print thinking animation shown while thinking blocks are hidden
+pub fn print_thinking_animation(frame: usize, text: &str) {
+    if !stderr_is_tty() {
+        print_plain_status(text);
+        return;
+    }
+    let dots = ["", ".", "..", "..."];
+    let trail = dots[frame % dots.len()];
+    let icon = if emoji_enabled() { "🧠 " } else { "" };
+    eprint!("\r\x1B[K{}{}{}{}{}", Color::BrightMagenta.code(), icon, with_emoji_fallback(text), trail, Color::Reset.code());
     io::stderr().flush().unwrap();
 }
 
 /// Print progress bar
 pub fn print_progress_bar(current: usize, total: usize, width: usize) {
+    write_progress_bar(&mut io::stdout(), current, total, width).unwrap();
+}
+
+/// Draw a progress bar in place on `out`; anything past `total` shows as full
+pub fn write_progress_bar(out: &mut impl Write, current: usize, total: usize, width: usize) -> io::Result<()> {
+    let total = total.max(1);
+    let current = current.min(total);
     let progress = (current * width) / total;
     let bar: String = "█".repeat(progress) + &"░".repeat(width - progress);
     
     let percentage = (current * 100) / total;
-    print!("\r{}{} {}%{}", 
+    write!(out, "\r{}{} {}%{}", 
            Color::BrightGreen.code(), 
            bar, 
            percentage, 
-           Color::Reset.code());
-    io::stdout().flush().unwrap();
+           Color::Reset.code())?;
+    out.flush()
+}
+
+// Characters per token used to estimate generated tokens, as in the prompt size estimates
+const CHARS_PER_TOKEN: f64 = 4.0;
+const STREAM_PROGRESS_WIDTH: usize = 30;
+
+/// Progress of a streamed response against its max_tokens, estimated from the characters
+/// received so far and drawn as a progress bar on `out`
+pub struct StreamProgress<W: Write> {
+    out: W,
+    max_tokens: usize,
+    chars: usize,
+}
+
+impl<W: Write> StreamProgress<W> {
+    pub fn new(out: W, max_tokens: u64) -> Self {
+        Self { out, max_tokens: max_tokens.min(usize::MAX as u64) as usize, chars: 0 }
+    }
+
+    /// Count a received delta and redraw the bar
+    pub fn update(&mut self, delta: &str) -> io::Result<()> {
+        self.chars += delta.chars().count();
+        let tokens = (self.chars as f64 / CHARS_PER_TOKEN).ceil() as usize;
+        write_progress_bar(&mut self.out, tokens, self.max_tokens, STREAM_PROGRESS_WIDTH)
+    }
+
+    /// Clear the bar, handing back the output it was drawn on
+    pub fn finish(mut self) -> io::Result<W> {
+        write!(self.out, "\r\x1B[K")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
 }
 
 /// Display the OLA ASCII art with colors
@@ -206,3 +428,85 @@ pub fn startup_animation() {
     print_rainbow("🌊 Welcome to Ola - Your Ocean of AI Possibilities! 🌊");
     println!();
 }
+
+/// Indent a JSON object or array two spaces per level, keeping its key order and
+/// number formatting; with `highlight`, keys, strings, numbers and literals are
+/// colored with the current theme. Returns `None` for text that isn't a JSON
+/// object or array.
+pub fn format_json(text: &str, highlight: bool) -> Option<String> {
+    let text = text.trim();
+    if !text.starts_with(['{', '[']) || serde_json::from_str::<serde_json::Value>(text).is_err() {
+        return None;
+    }
+    let paint = |color: Color, token: &str| {
+        if highlight {
+            format!("{}{}{}", color.code(), token, Color::Reset.code())
+        } else {
+            token.to_string()
+        }
+    };
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    };
+    let next_token = |chars: &[char], from: usize| (from..chars.len()).find(|&j| !chars[j].is_whitespace());
+
+    // The text is valid JSON, so a single pass over its tokens is enough
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '"' => {
+                let start = i;
+                i += 1;
+                while chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                let token: String = chars[start..=i].iter().collect();
+                let is_key = next_token(&chars, i + 1).map(|j| chars[j]) == Some(':');
+                out.push_str(&paint(if is_key { Color::Cyan } else { Color::Green }, &token));
+            }
+            open @ ('{' | '[') => {
+                let close = if open == '{' { '}' } else { ']' };
+                match next_token(&chars, i + 1) {
+                    // Empty containers stay on one line
+                    Some(j) if chars[j] == close => {
+                        out.push(open);
+                        out.push(close);
+                        i = j;
+                    }
+                    _ => {
+                        depth += 1;
+                        out.push(open);
+                        newline(&mut out, depth);
+                    }
+                }
+            }
+            close @ ('}' | ']') => {
+                depth -= 1;
+                newline(&mut out, depth);
+                out.push(close);
+            }
+            ',' => {
+                out.push(',');
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            _ => {
+                // A number, true, false or null runs until the next delimiter
+                let start = i;
+                while i + 1 < chars.len() && !matches!(chars[i + 1], ',' | '}' | ']') && !chars[i + 1].is_whitespace() {
+                    i += 1;
+                }
+                let token: String = chars[start..=i].iter().collect();
+                let color = if token.starts_with(['t', 'f', 'n']) { Color::Magenta } else { Color::Yellow };
+                out.push_str(&paint(color, &token));
+            }
+        }
+        i += 1;
+    }
+    Some(out)
+}
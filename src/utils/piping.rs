@@ -1,16 +1,23 @@
 // Utilities for handling piped input/output
 use std::io::{self, Read};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
-/// Read content from standard input (stdin)
+/// Stdin cap used when `behavior.max_stdin_bytes` is 0
+pub const STDIN_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Read content from standard input (stdin), stopping after `max_bytes`.
+/// Input past the cap is left unread and a warning is printed.
 /// Returns the content as a String or an empty string if there's an error
-pub fn read_from_stdin() -> String {
-    // Check if stdin has data available
+pub fn read_from_stdin(max_bytes: usize) -> String {
     let stdin = io::stdin();
-    let mut handle = stdin.lock();
-    
-    let mut buffer = String::new();
-    match handle.read_to_string(&mut buffer) {
-        Ok(_) => buffer,
+    match read_capped(stdin.lock(), max_bytes) {
+        Ok((content, truncated)) => {
+            if truncated {
+                eprintln!("Warning: piped input exceeded max_stdin_bytes ({} bytes); the rest was ignored", max_bytes);
+            }
+            content
+        }
         Err(e) => {
             eprintln!("Error reading from stdin: {}", e);
             String::new()
@@ -18,6 +25,127 @@ pub fn read_from_stdin() -> String {
     }
 }
 
+/// Read at most `max_bytes` from `reader` as UTF-8, never holding more than the cap in
+/// memory. Returns the text and whether there was more input; a character split by the
+/// cap is dropped.
+pub fn read_capped(reader: impl Read, max_bytes: usize) -> io::Result<(String, bool)> {
+    let mut buffer = Vec::new();
+    reader.take(max_bytes as u64 + 1).read_to_end(&mut buffer)?;
+    let truncated = buffer.len() > max_bytes;
+    if truncated {
+        buffer.truncate(max_bytes);
+        if let Err(e) = std::str::from_utf8(&buffer) {
+            if e.error_len().is_none() {
+                buffer.truncate(e.valid_up_to());
+            }
+        }
+    }
+    let content = String::from_utf8(buffer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok((content, truncated))
+}
+
+/// Replace every run of whitespace, including newlines, with a single space
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Output cap for `--context-cmd` when neither `command_max_output_bytes` nor a project
+/// context budget is configured
+pub const CONTEXT_COMMAND_MAX_BYTES: usize = 64 * 1024;
+
+/// How long a `--context-cmd` command may run before it is killed, unless `command_timeout_secs` is set
+pub const CONTEXT_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run a shell command and capture its stdout for use as prompt context.
+/// The command is killed after `timeout`; output beyond `max_bytes` is cut off with a note
+/// in the text and a warning on stderr,
+/// and terminal escape sequences and control characters are removed.
+pub fn run_context_command(command: &str, timeout: Duration, max_bytes: usize) -> Result<String, String> {
+    let mut child = shell_command(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run `{}`: {}", command, e))?;
+
+    // Read on separate threads so a chatty command can't block on a full pipe
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(format!("could not capture the output of `{}`", command));
+    };
+    // Anything past the caps is drained so the command isn't killed by a closed pipe
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let mut capped = stdout.take(max_bytes as u64 + 1);
+        let _ = capped.read_to_end(&mut buffer);
+        let _ = io::copy(&mut capped.into_inner(), &mut io::sink());
+        buffer
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buffer = String::new();
+        let mut capped = stderr.take(4096);
+        let _ = capped.read_to_string(&mut buffer);
+        let _ = io::copy(&mut capped.into_inner(), &mut io::sink());
+        buffer
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("`{}` timed out after {}s and was killed", command, timeout.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("could not wait for `{}`: {}", command, e)),
+        }
+    };
+
+    let mut output = stdout_reader.join().unwrap_or_default();
+    let errors = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        let detail = errors.trim();
+        return Err(if detail.is_empty() {
+            format!("`{}` failed ({})", command, status)
+        } else {
+            format!("`{}` failed ({}): {}", command, status, detail)
+        });
+    }
+
+    let truncated = output.len() > max_bytes;
+    output.truncate(max_bytes);
+    let mut text = sanitize_command_output(&String::from_utf8_lossy(&output));
+    if truncated {
+        eprintln!("Warning: output of `{}` exceeded {} bytes; the rest was dropped", command, max_bytes);
+        text.push_str(&format!("\n[Output truncated at {} bytes]", max_bytes));
+    }
+    Ok(text)
+}
+
+// Run through the platform shell so pipes and quoting behave as typed
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+// Drop ANSI escape sequences and control characters other than newlines and tabs
+fn sanitize_command_output(text: &str) -> String {
+    let ansi = regex::Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").expect("valid ANSI pattern");
+    ansi.replace_all(text, "")
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+        .collect()
+}
 
 /// Append an entry to a log file in JSON Lines format
 pub fn append_to_log(filename: &str, entry: &str) -> io::Result<()> {
@@ -0,0 +1,32 @@
+// Scrubs secrets out of a prompt before it is sent to a provider
+use regex::Regex;
+
+/// Text that replaces each match
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Token shapes that are always redacted: AWS access key IDs, `sk-` API keys
+/// (OpenAI, Anthropic), GitHub tokens and bearer tokens
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+    r"\bsk-[A-Za-z0-9_-]{16,}",
+    r"\bgh[pousr]_[A-Za-z0-9]{36,}\b",
+    r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]{16,}",
+];
+
+/// Replace every match of the built-in patterns and `patterns` with `[REDACTED]`.
+/// Returns the scrubbed text and the number of replacements, or an error naming
+/// the first pattern that isn't a valid regex.
+pub fn redact(text: &str, patterns: &[String]) -> Result<(String, usize), String> {
+    let mut text = text.to_string();
+    let mut count = 0;
+    let all = DEFAULT_PATTERNS.iter().copied().chain(patterns.iter().map(String::as_str));
+    for pattern in all {
+        let re = Regex::new(pattern).map_err(|e| format!("Invalid redact pattern '{}': {}", pattern, e))?;
+        let matches = re.find_iter(&text).filter(|m| m.as_str() != REDACTED).count();
+        if matches > 0 {
+            count += matches;
+            text = re.replace_all(&text, REDACTED).into_owned();
+        }
+    }
+    Ok((text, count))
+}
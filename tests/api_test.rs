@@ -0,0 +1,498 @@
+use ola::api::{Anthropic, ApiClient, ApiError, Completion, FileSink, Gemini, NetworkError, Ollama, OpenAI, Provider, RequestOptions, ResponseSink, RetryPolicy, StreamError, Tee, WriterSink};
+use std::cell::RefCell;
+use std::rc::Rc;
+use serde_json::json;
+use std::time::Duration;
+
+#[path = "common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+use common::TestHome;
+
+// Collects streamed deltas in memory
+#[derive(Default)]
+struct BufferSink {
+    text: String,
+}
+
+impl ResponseSink for BufferSink {
+    fn write(&mut self, delta: &str) -> std::io::Result<()> {
+        self.text.push_str(delta);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_anthropic_max_tokens_clamped_to_model_ceiling() {
+    let provider = Anthropic::new("test-key", None).with_max_tokens(Some(100_000));
+
+    let payload = provider.build_payload("Hello", "claude-3-haiku-20240307", false);
+    assert_eq!(payload["max_tokens"], 4096);
+
+    let payload = provider.build_payload("Hello", "claude-sonnet-4-20250514", false);
+    assert_eq!(payload["max_tokens"], 64000);
+}
+
+#[test]
+fn test_anthropic_extended_thinking_payload() {
+    let provider = Anthropic::new("test-key", None).with_max_tokens(Some(8000)).with_temperature(Some(0.2)).with_extended_thinking(true);
+
+    let payload = provider.build_payload("Hello", "claude-sonnet-4-20250514", false);
+    assert_eq!(payload["thinking"], json!({ "type": "enabled", "budget_tokens": 4000 }));
+    assert_eq!(payload["max_tokens"], 8000);
+    // Extended thinking doesn't accept a temperature
+    assert!(payload.get("temperature").is_none());
+
+    // The budget never drops below the API minimum, and max_tokens makes room for an answer
+    let payload = Anthropic::new("test-key", None).with_max_tokens(Some(1000)).with_extended_thinking(true)
+        .build_payload("Hello", "claude-sonnet-4-20250514", false);
+    assert_eq!(payload["thinking"]["budget_tokens"], 1024);
+    assert_eq!(payload["max_tokens"], 2048);
+}
+
+#[test]
+fn test_anthropic_streamed_thinking_is_wrapped_in_think_tags() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/messages")
+        .with_header("content-type", "text/event-stream")
+        .with_body(concat!(
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"thinking\",\"thinking\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"Check \"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"the moon.\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"text_delta\",\"text\":\"Noon.\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+        ))
+        .create();
+
+    let provider = Anthropic::new("test-key", Some(&server.url())).with_extended_thinking(true);
+    let mut streamed = BufferSink::default();
+    let completion = provider.send_prompt("Hello", "claude-sonnet-4-20250514", Some(&mut streamed)).unwrap();
+    assert_eq!(completion.content, "<think>Check the moon.</think>\n\nNoon.");
+    assert_eq!(streamed.text, completion.content);
+    mock.assert();
+}
+
+#[test]
+fn test_anthropic_max_tokens_within_ceiling_is_kept() {
+    let provider = Anthropic::new("test-key", None).with_max_tokens(Some(3000));
+    let payload = provider.build_payload("Hello", "claude-3-opus-20240229", true);
+    assert_eq!(payload["max_tokens"], 3000);
+
+    let default_provider = Anthropic::new("test-key", None);
+    let payload = default_provider.build_payload("Hello", "claude-3-opus-20240229", true);
+    assert_eq!(payload["max_tokens"], 2048);
+}
+
+#[test]
+fn test_openai_reasoning_effort_only_sent_to_capable_models() {
+    let provider = OpenAI::new("test-key", None).with_reasoning_effort(Some("high".to_string()));
+
+    let payload = provider.build_payload("Hello", "o3-mini", false);
+    assert_eq!(payload["reasoning_effort"], "high");
+
+    let payload = provider.build_payload("Hello", "gpt-4", false);
+    assert!(payload.get("reasoning_effort").is_none());
+
+    let default_provider = OpenAI::new("test-key", None);
+    let payload = default_provider.build_payload("Hello", "o3-mini", false);
+    assert!(payload.get("reasoning_effort").is_none());
+}
+
+#[test]
+fn test_request_options_from_additional_settings() {
+    let settings = json!({ "base_url": "http://localhost", "max_tokens": 8000 });
+    let options = RequestOptions::from_additional_settings(Some(&settings));
+    assert_eq!(options.max_tokens, Some(8000));
+
+    let options = RequestOptions::from_additional_settings(None);
+    assert_eq!(options.max_tokens, None);
+
+    let settings = json!({ "max_retries": 5, "retry_delay_ms": 250 });
+    let policy = RetryPolicy::from_options(&RequestOptions::from_additional_settings(Some(&settings)));
+    assert_eq!(policy.max_retries, 5);
+    assert_eq!(policy.base_delay, Duration::from_millis(250));
+}
+
+#[test]
+fn test_normalize_base_url() {
+    use ola::config::normalize_base_url;
+
+    assert_eq!(normalize_base_url("https://api.openai.com/"), "https://api.openai.com");
+    assert_eq!(normalize_base_url("https://proxy.example.com/openai//"), "https://proxy.example.com/openai");
+    assert_eq!(normalize_base_url("api.example.com"), "https://api.example.com");
+    assert_eq!(normalize_base_url("localhost:11434"), "http://localhost:11434");
+    assert_eq!(normalize_base_url(" http://10.0.0.5:11434 "), "http://10.0.0.5:11434");
+}
+
+#[test]
+fn test_validate_base_url() {
+    use ola::config::validate_base_url;
+
+    assert!(validate_base_url("https://api.openai.com").is_ok());
+    assert!(validate_base_url("localhost:11434/").is_ok());
+    assert!(validate_base_url("ftp://example.com").is_err());
+    assert!(validate_base_url("https://").is_err());
+}
+
+#[test]
+fn test_trailing_slash_and_schemeless_base_url_hit_endpoint() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+
+    let with_slash = format!("{}/", server.url());
+    let client = ola::ApiClient::new("OpenAI", "sk-test", Some(&with_slash)).unwrap();
+    assert_eq!(client.stream_prompt("Hello", "gpt-4o").unwrap().content, "ok");
+
+    let schemeless = server.url().trim_start_matches("http://").to_string();
+    let client = ola::ApiClient::new("OpenAI", "sk-test", Some(&schemeless)).unwrap();
+    assert_eq!(client.stream_prompt("Hello", "gpt-4o").unwrap().content, "ok");
+
+    mock.assert();
+}
+
+#[test]
+fn test_unauthorized_is_not_retried() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_status(401)
+        .with_body("{\"error\":{\"message\":\"Incorrect API key provided\"}}")
+        .expect(1)
+        .create();
+
+    let options = RequestOptions { max_retries: Some(3), retry_delay_ms: Some(1), ..Default::default() };
+    let client = ola::ApiClient::with_options("OpenAI", "sk-bad", Some(&server.url()), &options).unwrap();
+    let error = client.stream_prompt("Hello", "gpt-4o").unwrap_err();
+
+    let api_error = error.downcast_ref::<ApiError>().expect("expected an ApiError");
+    assert_eq!(api_error.status, 401);
+    assert!(api_error.hint().unwrap().contains("ola configure"));
+    mock.assert();
+}
+
+#[test]
+fn test_rate_limit_is_retried() {
+    let mut server = mockito::Server::new();
+    let limited = server.mock("POST", "/v1/chat/completions")
+        .with_status(429)
+        .with_body("rate limited")
+        .expect(2)
+        .create();
+    let ok = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let options = RequestOptions { max_retries: Some(2), retry_delay_ms: Some(1), ..Default::default() };
+    let client = ola::ApiClient::with_options("OpenAI", "sk-test", Some(&server.url()), &options).unwrap();
+    assert_eq!(client.stream_prompt("Hello", "gpt-4o").unwrap().content, "ok");
+
+    limited.assert();
+    ok.assert();
+}
+
+#[test]
+fn test_malformed_stream_lines_are_skipped() {
+    let mut server = mockito::Server::new();
+    let openai = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\ndata: {not json\n\ndata: {\"choices\":[{\"delta\":{\"content\":\" world\"},\"finish_reason\":\"stop\"}]}\n\ndata: [DONE]\n\n")
+        .create();
+    let ollama = server.mock("POST", "/api/generate")
+        .with_header("content-type", "application/x-ndjson")
+        .with_body("{\"response\":\"Hello\",\"done\":false}\n{\"response\":\"trunc\n{\"response\":\" world\",\"done\":true,\"done_reason\":\"stop\"}\n")
+        .create();
+
+    let client = ola::ApiClient::new("OpenAI", "sk-test", Some(&server.url())).unwrap();
+    let completion = client.stream_prompt("Hello", "gpt-4o").unwrap();
+    assert_eq!(completion.content, "Hello world");
+    assert_eq!(completion.finish_reason.as_deref(), Some("stop"));
+
+    let client = ola::ApiClient::new("Ollama", "", Some(&server.url())).unwrap();
+    let completion = client.send_prompt("Hello", "llama3").unwrap();
+    assert_eq!(completion.content, "Hello world");
+    openai.assert();
+    ollama.assert();
+}
+
+#[test]
+fn test_interrupted_stream_keeps_partial_response() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    // A server that sends one event of a chunked body, then closes without the final chunk
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        // Read the whole request first so closing the connection can't reset it
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        reader.read_exact(&mut vec![0; content_length]).unwrap();
+
+        let event = "data: {\"delta\":{\"text\":\"Partial answer\"}}\n\n";
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+            event.len(),
+            event
+        )
+        .unwrap();
+    });
+
+    let options = RequestOptions { max_retries: Some(0), ..Default::default() };
+    let client = ola::ApiClient::with_options("Anthropic", "sk-test", Some(&url), &options).unwrap();
+    let error = client.stream_prompt("Hello", "claude-3-5-sonnet-20241022").unwrap_err();
+    server.join().unwrap();
+
+    let stream_error = error.downcast_ref::<StreamError>().expect("expected a StreamError");
+    assert_eq!(stream_error.provider, "Anthropic");
+    assert_eq!(stream_error.partial.content, "Partial answer");
+}
+
+// Records whether each request was streamed
+struct NonStreamingProvider {
+    requests: Rc<RefCell<Vec<bool>>>,
+}
+
+impl Provider for NonStreamingProvider {
+    fn send_prompt(&self, _prompt: &str, _model: &str, sink: Option<&mut dyn ResponseSink>) -> Result<Completion, Box<dyn std::error::Error>> {
+        self.requests.borrow_mut().push(sink.is_some());
+        Ok(Completion { content: "whole response".to_string(), finish_reason: Some("stop".to_string()) })
+    }
+
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_stream_prompt_falls_back_when_provider_cannot_stream() {
+    let requests = Rc::new(RefCell::new(Vec::new()));
+    let provider = NonStreamingProvider { requests: Rc::clone(&requests) };
+    let client = ApiClient::from_provider("Stub", Box::new(provider), &RequestOptions::default());
+    let completion = client.stream_prompt("Hello", "stub-model").unwrap();
+    assert_eq!(completion.content, "whole response");
+    assert_eq!(*requests.borrow(), vec![false]);
+
+    // Built-in providers report their own streaming support
+    assert!(!Gemini::new("test-key", None).supports_streaming());
+    assert!(OpenAI::new("test-key", None).supports_streaming());
+    assert!(!OpenAI::new("test-key", None).with_streaming(false).supports_streaming());
+}
+
+const OLLAMA_MODEL_NOT_FOUND: &str = r#"{"error":"model \"llama3\" not found, try pulling it first"}"#;
+
+#[test]
+fn test_ollama_missing_model_suggests_pull() {
+    let mut server = mockito::Server::new();
+    let _m = server.mock("POST", "/api/generate")
+        .with_status(404)
+        .with_body(OLLAMA_MODEL_NOT_FOUND)
+        .create();
+
+    let client = ola::ApiClient::new("Ollama", "", Some(&server.url())).unwrap();
+    let error = client.send_prompt("Hello", "llama3").unwrap_err();
+    let api_error = error.downcast_ref::<ApiError>().expect("expected an ApiError");
+    assert_eq!(api_error.missing_ollama_model().as_deref(), Some("llama3"));
+    assert!(api_error.hint().unwrap().contains("ollama pull llama3"));
+
+    // Other 404s, and other providers, get no pull suggestion
+    let not_found = ApiError { provider: "Ollama".to_string(), status: 404, body: "404 page not found".to_string() };
+    assert_eq!(not_found.missing_ollama_model(), None);
+    let openai = ApiError { provider: "OpenAI".to_string(), status: 404, body: OLLAMA_MODEL_NOT_FOUND.to_string() };
+    assert_eq!(openai.missing_ollama_model(), None);
+}
+
+#[test]
+fn test_ollama_auto_pull_pulls_and_retries() {
+    let mut server = mockito::Server::new();
+    let missing = server.mock("POST", "/api/generate")
+        .with_status(404)
+        .with_body(OLLAMA_MODEL_NOT_FOUND)
+        .expect(1)
+        .create();
+    let pull = server.mock("POST", "/api/pull")
+        .match_body(mockito::Matcher::PartialJson(json!({"model": "llama3"})))
+        .with_body("{\"status\":\"pulling manifest\"}\n{\"status\":\"success\"}\n")
+        .expect(1)
+        .create();
+    let generated = server.mock("POST", "/api/generate")
+        .with_body("{\"response\":\"Pulled answer\",\"done\":true,\"done_reason\":\"stop\"}\n")
+        .expect(1)
+        .create();
+
+    let provider = Ollama::new(Some(&server.url())).with_auto_pull(true);
+    let completion = provider.send_prompt("Hello", "llama3", None).unwrap();
+    assert_eq!(completion.content, "Pulled answer");
+    missing.assert();
+    pull.assert();
+    generated.assert();
+}
+
+#[test]
+fn test_temperature_omitted_for_models_that_reject_it() {
+    let defaults = ola::settings::DefaultSettings { temperature: Some(0.2), max_tokens: Some(500), ..Default::default() };
+    let options = RequestOptions::from_additional_settings(None).with_setting_defaults(&defaults);
+    let provider = OpenAI::new("test-key", None).with_sampling(options.sampling());
+
+    let payload = provider.build_payload("Hello", "o3-mini", false);
+    assert!(payload.get("temperature").is_none());
+    assert_eq!(payload["max_completion_tokens"], 500);
+
+    let payload = provider.build_payload("Hello", "gpt-4o", false);
+    assert_eq!(payload["temperature"], 0.2);
+    assert_eq!(payload["max_tokens"], 500);
+
+    // The provider's additional_settings win over the global defaults
+    let settings = json!({ "temperature": 0.9 });
+    let options = RequestOptions::from_additional_settings(Some(&settings)).with_setting_defaults(&defaults);
+    assert_eq!(options.temperature, Some(0.9));
+    assert_eq!(options.max_tokens, Some(500));
+}
+
+#[test]
+fn test_model_sampling_defaults_fill_unset_values() {
+    let payload = Gemini::new("test-key", None).build_payload("Hello", "gemini-1.5-pro");
+    assert_eq!(payload["generationConfig"]["temperature"], 0.7);
+    assert_eq!(payload["generationConfig"]["maxOutputTokens"], 2048);
+
+    let payload = Gemini::new("test-key", None).build_payload("Hello", "gemini-2.0-flash");
+    assert_eq!(payload["generationConfig"]["maxOutputTokens"], 1024);
+
+    let payload = Ollama::new(None).build_payload("Hello", "llama3", true);
+    assert_eq!(payload["options"]["num_predict"], 2048);
+    assert!(payload["options"].get("temperature").is_none());
+
+    // Nothing is sent for OpenAI models without recommended defaults
+    let payload = OpenAI::new("test-key", None).build_payload("Hello", "gpt-4o", false);
+    assert!(payload.get("temperature").is_none());
+    assert!(payload.get("max_tokens").is_none());
+}
+
+#[test]
+fn test_stream_to_file_appends_each_delta_as_it_arrives() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let stream_file = temp_dir.path().join("out/partial.txt");
+    let watched = stream_file.clone();
+    let mut server = mockito::Server::new();
+    let _m = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_chunked_body(move |w| {
+            w.write_all(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n")?;
+            w.flush()?;
+            // The first delta must reach the file before the rest of the stream is sent
+            let started = std::time::Instant::now();
+            while std::fs::read_to_string(&watched).unwrap_or_default() != "Hello" {
+                if started.elapsed() > Duration::from_secs(5) {
+                    return Err(std::io::Error::other("first delta was not written to the file"));
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            w.write_all(b"data: {\"choices\":[{\"delta\":{\"content\":\" world\"},\"finish_reason\":\"stop\"}]}\n\ndata: [DONE]\n\n")
+        })
+        .create();
+
+    let provider = OpenAI::new("test-key", Some(&server.url()));
+    let completion = provider.send_prompt("Hello", "gpt-4o", Some(&mut FileSink::open(&stream_file))).unwrap();
+    assert_eq!(completion.content, "Hello world");
+    assert_eq!(std::fs::read_to_string(&stream_file).unwrap(), "Hello world");
+}
+
+#[test]
+fn test_tee_sends_one_stream_to_every_sink() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body(concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\", sinks\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        ))
+        .expect(1)
+        .create();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("response.txt");
+
+    let client = ApiClient::new("OpenAI", "test-key", Some(&server.url())).unwrap();
+    let mut stdout = Vec::new();
+    let mut clipboard = BufferSink::default();
+    let mut tee = Tee::new().with(WriterSink::new(&mut stdout)).with(FileSink::open(&path)).with(&mut clipboard);
+    let completion = client.stream_prompt_to("Hello", "gpt-4o", &mut tee).unwrap();
+    drop(tee);
+
+    assert_eq!(completion.content, "Hello, sinks");
+    assert_eq!(String::from_utf8(stdout).unwrap(), completion.content);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), completion.content);
+    assert_eq!(clipboard.text, completion.content);
+    mock.assert();
+}
+
+#[test]
+fn test_configured_gemini_provider_creates_gemini_client() {
+    let server = mockito::Server::new();
+    let temp_dir = TestHome::with_provider("Gemini", Some("gemini-1.5-flash"), &server.url()).create();
+
+    let old_home = std::env::var("HOME").ok();
+    std::env::set_var("HOME", temp_dir.path());
+    let client = ola::api::create_api_client_from_config();
+    if let Some(home) = old_home {
+        std::env::set_var("HOME", home);
+    }
+
+    assert_eq!(client.unwrap().provider_name(), "Gemini");
+}
+
+#[test]
+fn test_request_timeout_aborts_slow_responses() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_chunked_body(|w| {
+            std::thread::sleep(Duration::from_secs(2));
+            w.write_all(b"{\"choices\":[{\"message\":{\"content\":\"Too late\"}}]}")
+        })
+        .create();
+
+    let provider = OpenAI::new("test-key", Some(&server.url())).with_timeout(Some(Duration::from_millis(200)));
+    let error = provider.send_prompt("Hello", "gpt-4o", None).unwrap_err();
+    let error = error.downcast_ref::<reqwest::Error>().expect("a reqwest error");
+    assert!(error.is_timeout(), "expected a timeout, got {}", error);
+    mock.assert();
+
+    // 0 in the settings means no timeout at all, and unset keeps the default
+    let options = RequestOptions::from_additional_settings(Some(&json!({"request_timeout_secs": 0})));
+    assert_eq!(options.timeout(), None);
+    assert_eq!(RequestOptions::default().timeout(), Some(Duration::from_secs(120)));
+}
+
+#[test]
+fn test_unreachable_provider_error_names_host_and_cause() {
+    // Bind a port and release it so nothing is listening there
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let provider = Ollama::new(Some(&format!("http://127.0.0.1:{}", port)));
+
+    let error = provider.send_prompt("Hello", "llama3", None).unwrap_err();
+    let message = error.to_string();
+    assert!(error.downcast_ref::<NetworkError>().is_some());
+    assert!(message.contains(&format!("127.0.0.1:{}", port)), "{}", message);
+    assert!(message.contains("Ollama"), "{}", message);
+    assert!(message.contains("connection refused"), "{}", message);
+    assert!(message.contains("base_url"), "{}", message);
+}
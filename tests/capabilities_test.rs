@@ -0,0 +1,96 @@
+use ola::capabilities::{capabilities, Capabilities};
+
+#[test]
+fn test_openai_known_models() {
+    let caps = capabilities("OpenAI", "gpt-4o-mini");
+    assert!(caps.vision);
+    assert!(caps.json_mode);
+    assert!(caps.system_prompt);
+    assert!(caps.streaming);
+    assert!(caps.stop_sequences);
+    assert!(caps.seed);
+    assert!(!caps.reasoning_effort);
+
+    let caps = capabilities("OpenAI", "o1-mini");
+    assert!(!caps.vision);
+    assert!(!caps.system_prompt);
+    assert!(!caps.stop_sequences);
+
+    assert!(capabilities("OpenAI", "o4-mini-high").reasoning_effort);
+}
+
+#[test]
+fn test_anthropic_known_models() {
+    let caps = capabilities("Anthropic", "claude-3-5-sonnet-20241022");
+    assert!(caps.vision);
+    assert!(caps.system_prompt);
+    assert!(caps.stop_sequences);
+    assert!(!caps.json_mode);
+    assert!(!caps.seed);
+}
+
+#[test]
+fn test_gemini_and_ollama_models() {
+    let caps = capabilities("Gemini", "gemini-1.5-flash");
+    assert!(caps.vision);
+    assert!(caps.json_mode);
+    assert!(caps.system_prompt);
+
+    assert!(capabilities("Ollama", "llava:13b").vision);
+    let caps = capabilities("Ollama", "llama3:8b");
+    assert!(!caps.vision);
+    assert!(caps.json_mode);
+}
+
+#[test]
+fn test_unknown_models_fall_back_conservatively() {
+    // Unknown model of a known provider only gets provider-wide features
+    let caps = capabilities("Anthropic", "mystery-model");
+    assert!(caps.streaming);
+    assert!(!caps.vision);
+    assert!(!caps.json_mode);
+
+    // Unknown provider gets nothing
+    assert_eq!(capabilities("SomethingElse", "gpt-4o"), Capabilities::default());
+}
+
+#[test]
+fn test_visible_thinking_by_provider() {
+    assert!(capabilities("Ollama", "deepseek-r1:8b").visible_thinking);
+    assert!(!capabilities("OpenAI", "o3").visible_thinking);
+    assert!(!capabilities("Anthropic", "claude-sonnet-4-20250514").visible_thinking);
+    assert!(!capabilities("Gemini", "gemini-1.5-pro").visible_thinking);
+}
+
+#[test]
+fn test_context_windows() {
+    use ola::capabilities::context_window;
+    assert_eq!(context_window("OpenAI", "gpt-4"), Some(8_192));
+    assert_eq!(context_window("OpenAI", "gpt-4o-mini"), Some(128_000));
+    assert_eq!(context_window("Anthropic", "claude-3-5-sonnet-20241022"), Some(200_000));
+    assert_eq!(context_window("Ollama", "llama3"), None);
+    assert_eq!(context_window("OpenAI", "unknown-model"), None);
+}
+
+#[test]
+fn test_temperature_support_and_sampling_defaults() {
+    use ola::capabilities::{resolve_sampling, Sampling};
+    assert!(!capabilities("OpenAI", "o3-mini").temperature);
+    assert!(!capabilities("OpenAI", "gpt-5").temperature);
+    assert!(capabilities("OpenAI", "gpt-4o").temperature);
+    assert!(capabilities("Anthropic", "claude-3-haiku-20240307").temperature);
+
+    let requested = Sampling { temperature: Some(0.3), max_tokens: None };
+    assert_eq!(resolve_sampling("OpenAI", "o1", requested), Sampling::default());
+    assert_eq!(resolve_sampling("Gemini", "gemini-1.5-flash", requested), Sampling { temperature: Some(0.3), max_tokens: Some(1024) });
+}
+
+#[test]
+fn test_model_families_by_provider() {
+    use ola::capabilities::is_provider_model;
+    assert_eq!(is_provider_model("OpenAI", "gpt-5"), Some(true));
+    assert_eq!(is_provider_model("OpenAI", "o4-mini"), Some(true));
+    assert_eq!(is_provider_model("Anthropic", "gpt-5"), Some(false));
+    assert_eq!(is_provider_model("Gemini", "gemini-2.5-pro"), Some(true));
+    assert_eq!(is_provider_model("Ollama", "gpt-5"), None);
+}
@@ -0,0 +1,30 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::tempdir;
+
+#[test]
+fn test_init_with_scripted_answers_sets_everything_up() {
+    let temp_dir = tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    for var in ["OPENAI_API_KEY", "ANTHROPIC_API_KEY", "GEMINI_API_KEY", "GOOGLE_API_KEY"] {
+        cmd.env_remove(var);
+    }
+    cmd.env("HOME", temp_dir.path())
+        .args([
+            "init", "--provider", "OpenAI", "--api-key", "sk-test", "--model", "gpt-4o",
+            "--default-model", "gpt-4o", "--logging", "false", "--project", "true",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ola is ready"))
+        .stdout(predicate::str::contains("ola prompt -g"));
+
+    let settings = std::fs::read_to_string(temp_dir.path().join(".ola/settings.yaml")).unwrap();
+    let settings: serde_yaml::Value = serde_yaml::from_str(&settings).unwrap();
+    assert_eq!(settings["active_provider"], "OpenAI");
+    assert_eq!(settings["providers"][0]["model"], "gpt-4o");
+    assert_eq!(settings["default_model"], "gpt-4o");
+    assert_eq!(settings["behavior"]["enable_logging"], false);
+    assert!(temp_dir.path().join(".ola/data/projects/default").is_dir());
+}
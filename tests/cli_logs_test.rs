@@ -0,0 +1,116 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::tempdir;
+
+#[path = "common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+use common::TestHome;
+
+#[test]
+fn test_logs_summary_prints_one_line_per_day() {
+    let temp_dir = tempdir().unwrap();
+    std::fs::write(
+        temp_dir.path().join("sessions.jsonl"),
+        concat!(
+            r#"{"timestamp":"2024-05-01T12:00:00+00:00","model":"gpt-4o","output_length":100}"#, "\n",
+            r#"{"timestamp":"2024-05-02T12:00:00+00:00","model":"gpt-4o","output_length":20}"#, "\n",
+            r#"{"timestamp":"2024-05-02T13:00:00+00:00","model":"llama3","output_length":30}"#, "\n",
+        ),
+    ).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("TZ", "UTC")
+        .current_dir(temp_dir.path())
+        .args(["logs", "summary"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2024-05-01  1 session  100 chars  models: gpt-4o (1)"))
+        .stdout(predicate::str::contains("2024-05-02  2 sessions  50 chars  models: gpt-4o (1), llama3 (1)"));
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("TZ", "UTC")
+        .current_dir(temp_dir.path())
+        .args(["logs", "summary", "--since", "2024-05-02"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2024-05-01").not());
+}
+
+#[test]
+fn test_logs_export_writes_csv() {
+    let temp_dir = tempdir().unwrap();
+    std::fs::write(
+        temp_dir.path().join("sessions.jsonl"),
+        concat!(
+            r#"{"timestamp":"2024-05-01T12:00:00+00:00","provider":"OpenAI","model":"gpt-4o","goals":"Compare A, B","output_length":100,"tokens":40,"cost":0.002}"#, "\n",
+            r#"{"timestamp":"2024-05-02T12:00:00+00:00","model":"llama3","output_length":30}"#, "\n",
+        ),
+    ).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("TZ", "UTC")
+        .current_dir(temp_dir.path())
+        .args(["logs", "export", "--csv", "sessions.csv"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Exported 2 sessions"));
+
+    let csv = std::fs::read_to_string(temp_dir.path().join("sessions.csv")).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines, vec![
+        "timestamp,provider,model,goals,output_length,tokens,cost",
+        "2024-05-01T12:00:00+00:00,OpenAI,gpt-4o,\"Compare A, B\",100,40,0.002",
+        "2024-05-02T12:00:00+00:00,,llama3,,30,,",
+    ]);
+}
+
+#[test]
+fn test_prompt_tags_are_logged_and_filterable() {
+    let mut server = mockito::Server::new();
+    let _mock = server.mock("POST", "/api/generate")
+        .with_body("{\"response\":\"Tagged answer\",\"done\":true}\n")
+        .create();
+
+    let temp_dir = tempdir().unwrap();
+    let log_path = temp_dir.path().join("sessions.jsonl");
+    std::fs::write(
+        &log_path,
+        concat!(r#"{"timestamp":"2024-05-01T12:00:00+00:00","model":"gpt-4o","output_length":100}"#, "\n"),
+    ).unwrap();
+    TestHome::with_provider("Ollama", Some("llama3"), &server.url())
+        .behavior("enable_logging: true")
+        .behavior(&format!("log_file: \"{}\"", log_path.display()))
+        .write(temp_dir.path());
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Review this", "--quiet", "--tag", "review", "--tag", "client-a"])
+        .assert()
+        .success();
+
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+    assert_eq!(entry["tags"], serde_json::json!(["review", "client-a"]));
+
+    let summary = |tags: &[&str]| {
+        let mut cmd = Command::cargo_bin("ola").unwrap();
+        cmd.env("HOME", temp_dir.path())
+            .env("TZ", "UTC")
+            .current_dir(temp_dir.path())
+            .args(["logs", "summary"]);
+        for tag in tags {
+            cmd.args(["--tag", tag]);
+        }
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+    assert!(summary(&[]).contains("2024-05-01"));
+    let tagged = summary(&["review", "client-a"]);
+    assert!(tagged.contains("1 session") && tagged.contains("llama3 (1)"));
+    assert!(!tagged.contains("2024-05-01"));
+    assert!(summary(&["review", "client-b"]).contains("No sessions logged"));
+}
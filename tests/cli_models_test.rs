@@ -4,6 +4,11 @@ use std::fs::{self, File};
 use std::io::Write;
 use tempfile::{tempdir, TempDir};
 
+#[path = "common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+use common::TestHome;
+
 // Helper function to create a temporary config file with a provider
 fn setup_temp_config_dir() -> TempDir {
     let temp_dir = tempdir().unwrap();
@@ -99,4 +104,163 @@ fn test_models_quiet_mode() {
 fn test_models_with_default_provider() {
     // Test skipped since it requires actual API access
     // In a real implementation, we would need to mock the API responses
-}
\ No newline at end of file
+}
+#[test]
+fn test_models_plain_progress_when_stderr_not_tty() {
+    // assert_cmd captures stderr through a pipe, so animations must fall back to plain lines
+    let temp_dir = tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .arg("models")
+        .arg("--provider")
+        .arg("Anthropic")
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Fetching available models for provider: Anthropic"));
+    assert!(!stderr.contains('\r'));
+    assert!(!stderr.contains("\x1B[K"));
+}
+
+// Write a settings file with an active provider into a temporary HOME
+fn setup_active_provider_home(provider: &str) -> TempDir {
+    let temp_dir = tempdir().unwrap();
+    let config_dir = temp_dir.path().join(".ola");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_content = format!(
+        "active_provider: \"{0}\"\nproviders:\n  - provider: \"{0}\"\n    api_key: \"test_key\"\n    model: null\n    additional_settings: null\n",
+        provider
+    );
+    fs::write(config_dir.join("settings.yaml"), config_content).unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_models_format_count() {
+    let temp_dir = setup_active_provider_home("Anthropic");
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .arg("models")
+        .arg("--format")
+        .arg("count")
+        .assert()
+        .success()
+        .stdout("5\n");
+}
+
+#[test]
+fn test_models_format_csv() {
+    let temp_dir = setup_active_provider_home("Gemini");
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .arg("models")
+        .arg("--format")
+        .arg("csv")
+        .assert()
+        .success()
+        .stdout("gemini-1.5-pro,gemini-1.5-flash,gemini-1.0-pro,gemini-1.0-pro-vision\n");
+}
+
+#[test]
+fn test_models_quiet_matches_builtin_lists() {
+    let temp_dir = tempdir().unwrap();
+
+    for provider in ["OpenAI", "Anthropic", "Gemini"] {
+        let mut cmd = Command::cargo_bin("ola").unwrap();
+        let output = cmd.env("HOME", temp_dir.path())
+            .args(["models", "--provider", provider, "--quiet"])
+            .output()
+            .expect("Failed to execute command");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let listed: Vec<&str> = stdout.lines().collect();
+        assert_eq!(listed, ola::config::builtin_models(provider), "model list drifted for {}", provider);
+    }
+}
+
+// Home directory whose Ollama provider points at the given server
+fn setup_ollama_home(base_url: &str) -> TempDir {
+    TestHome::with_provider("Ollama", Some("llama3"), base_url).create()
+}
+
+#[test]
+fn test_models_loaded_lists_only_resident_ollama_models() {
+    let mut server = mockito::Server::new();
+    let ps = server.mock("GET", "/api/ps")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"models":[{"name":"llama3:latest","model":"llama3:latest","size":5137025024,"expires_at":"2030-06-04T14:38:31Z","size_vram":5137025024}]}"#)
+        .expect(2)
+        .create();
+    let temp_dir = setup_ollama_home(&server.url());
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["models", "--provider", "Ollama", "--loaded", "--quiet"])
+        .assert()
+        .success()
+        .stdout("llama3:latest\n");
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["models", "--provider", "Ollama", "--loaded"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("llama3:latest (4.8 GB, expires 2030-06-04T14:38:31Z)"));
+    ps.assert();
+}
+
+#[test]
+fn test_models_loaded_falls_back_without_api_ps() {
+    let mut server = mockito::Server::new();
+    let _ps = server.mock("GET", "/api/ps").with_status(404).create();
+    let temp_dir = setup_ollama_home(&server.url());
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["models", "--provider", "Ollama", "--loaded", "--quiet"])
+        .assert()
+        .stderr(predicate::str::contains("could not query loaded Ollama models"));
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["models", "--provider", "OpenAI", "--loaded"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--loaded is only supported for Ollama"));
+}
+
+#[test]
+fn test_models_json_includes_ollama_metadata() {
+    let mut server = mockito::Server::new();
+    let tags = server.mock("GET", "/api/tags")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"models":[{"name":"llama3:latest","modified_at":"2030-05-01T10:00:00Z","size":4661224676},{"name":"phi3:mini","size":2176178913}]}"#)
+        .expect(1)
+        .create();
+    let temp_dir = setup_ollama_home(&server.url());
+
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["models", "--provider", "Ollama", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let models: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(models, serde_json::json!([
+        { "name": "llama3:latest", "size": 4661224676u64, "modified_at": "2030-05-01T10:00:00Z" },
+        { "name": "phi3:mini", "size": 2176178913u64 },
+    ]));
+    tags.assert();
+
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["models", "--provider", "OpenAI", "--json"])
+        .output()
+        .unwrap();
+    let models: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(models[0]["name"], ola::config::builtin_models("OpenAI")[0]);
+}
@@ -0,0 +1,118 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+// Export the settings of the profile in use and return the active provider
+fn exported_provider(home: &Path, profile: Option<&str>) -> String {
+    let export_file = home.join("exported.yaml");
+    let mut cmd = ola(home);
+    if let Some(profile) = profile {
+        cmd.args(["--profile", profile]);
+    }
+    cmd.args(["settings", "export"]).arg(&export_file).assert().success();
+    let exported: serde_yaml::Value = serde_yaml::from_str(&fs::read_to_string(&export_file).unwrap()).unwrap();
+    exported["active_provider"].as_str().unwrap_or_default().to_string()
+}
+
+// ola with HOME pointed at a temporary directory and no profile from the environment
+fn ola(home: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", home).env_remove("OLA_HOME").env_remove("OLA_PROFILE");
+    cmd
+}
+
+#[test]
+fn test_profiles_are_isolated() {
+    let temp_dir = tempdir().unwrap();
+    let home = temp_dir.path();
+    let base = home.join(".ola");
+    fs::create_dir_all(&base).unwrap();
+    fs::write(
+        base.join("settings.yaml"),
+        "active_provider: \"Anthropic\"\nproviders:\n  - provider: \"Anthropic\"\n    api_key: \"sk-base\"\n    model: null\n    additional_settings: null\nbehavior:\n  enable_logging: false\n",
+    )
+    .unwrap();
+
+    for (name, provider) in [("work", "OpenAI"), ("personal", "Ollama")] {
+        ola(home).args(["profile", "create", name])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(format!("Created profile '{}'", name)));
+        fs::write(
+            base.join("profiles").join(name).join("settings.yaml"),
+            format!("active_provider: \"{}\"\nproviders:\n  - provider: \"{}\"\n    api_key: \"\"\n    model: null\n    additional_settings: null\nbehavior:\n  enable_logging: false\n", provider, provider),
+        )
+        .unwrap();
+    }
+
+    assert_eq!(exported_provider(home, Some("work")), "OpenAI");
+    assert_eq!(exported_provider(home, Some("personal")), "Ollama");
+    assert_eq!(exported_provider(home, None), "Anthropic");
+
+    // Switching the default profile persists; --profile still overrides it
+    ola(home).args(["profile", "use", "personal"]).assert().success();
+    assert_eq!(exported_provider(home, None), "Ollama");
+    assert_eq!(exported_provider(home, Some("work")), "OpenAI");
+    ola(home).args(["profile", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("* personal"))
+        .stdout(predicate::str::contains("  work"));
+
+    // Projects live in the profile too
+    ola(home).args(["--profile", "work", "project", "create", "--name", "Work project"]).assert().success();
+    assert!(base.join("profiles/work/data/projects").read_dir().unwrap().next().is_some());
+    assert!(!base.join("profiles/personal/data/projects").exists());
+
+    ola(home).args(["profile", "use", "default"]).assert().success();
+    assert_eq!(exported_provider(home, None), "Anthropic");
+
+    ola(home).args(["--profile", "missing", "settings", "export"]).arg(home.join("x.yaml"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Profile 'missing' not found"));
+    ola(home).args(["profile", "create", "work"]).assert().failure().stderr(predicate::str::contains("already exists"));
+    ola(home).args(["profile", "create", "../escape"]).assert().failure().stderr(predicate::str::contains("Invalid profile name"));
+}
+
+#[test]
+fn test_profile_delete_switches_back_to_default() {
+    let temp_dir = tempdir().unwrap();
+    let home = temp_dir.path();
+    let base = home.join(".ola");
+    fs::create_dir_all(&base).unwrap();
+    // A settings file with only top-level providers is the default profile
+    fs::write(
+        base.join("settings.yaml"),
+        "active_provider: \"Anthropic\"\nproviders:\n  - provider: \"Anthropic\"\n    api_key: \"sk-base\"\n    model: null\n    additional_settings: null\nbehavior:\n  enable_logging: false\n",
+    )
+    .unwrap();
+
+    ola(home).args(["profile", "create", "work"]).assert().success();
+    fs::write(
+        base.join("profiles/work/settings.yaml"),
+        "active_provider: \"OpenAI\"\nproviders:\n  - provider: \"OpenAI\"\n    api_key: \"\"\n    model: null\n    additional_settings: null\nbehavior:\n  enable_logging: false\n",
+    )
+    .unwrap();
+    ola(home).args(["profile", "use", "work"]).assert().success();
+    assert_eq!(exported_provider(home, None), "OpenAI");
+
+    ola(home).args(["profile", "delete", "work", "--force"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Deleted profile 'work'"))
+        .stdout(predicate::str::contains("Using the default profile again"));
+    assert!(!base.join("profiles/work").exists());
+    assert_eq!(exported_provider(home, None), "Anthropic");
+
+    ola(home).args(["profile", "delete", "work", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Profile 'work' not found"));
+    ola(home).args(["profile", "delete", "default", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("can't be deleted"));
+}
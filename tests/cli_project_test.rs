@@ -0,0 +1,295 @@
+use assert_cmd::Command;
+use mockito::Server;
+use std::fs;
+
+#[path = "common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+use common::TestHome;
+
+#[test]
+fn test_project_run_with_iterations() {
+    let mut server = Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Project answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["project", "run", "--goals", "Review the plan", "--iterations", "2"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Iteration 1/2"));
+    assert!(stdout.contains("Iteration 2/2"));
+    mock.assert();
+}
+
+#[test]
+fn test_project_show_verbose_previews_files() {
+    let temp_dir = TestHome::openai("http://127.0.0.1:1").create();
+    let notes = temp_dir.path().join("notes.txt");
+    fs::write(&notes, "First line of notes\nsecond\nthird\nfourth\n").unwrap();
+    let image = temp_dir.path().join("image.bin");
+    fs::write(&image, [0x89u8, 0x50, 0x4e, 0x47, 0xff, 0xfe, 0x00]).unwrap();
+
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "create", "--name", "Docs"])
+        .assert()
+        .success();
+    for file in [&notes, &image] {
+        Command::cargo_bin("ola").unwrap()
+            .env("HOME", temp_dir.path())
+            .args(["project", "upload", "--project", "Docs", "--file"])
+            .arg(file)
+            .assert()
+            .success();
+    }
+
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "show", "--project", "Docs", "--preview-lines", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("| First line of notes"));
+    assert!(stdout.contains("| second"));
+    assert!(!stdout.contains("| third"));
+    assert!(stdout.contains("| ... (2 more lines)"));
+    assert!(stdout.contains("[binary]"));
+
+    // Without --verbose only names and sizes are listed
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "show", "--project", "Docs"])
+        .output()
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("First line of notes"));
+}
+
+#[test]
+fn test_project_run_includes_only_selected_files() {
+    let mut server = Server::new();
+    let sent = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+    let captured = std::sync::Arc::clone(&sent);
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body_from_request(move |request| {
+            *captured.lock().unwrap() = request.utf8_lossy_body().unwrap().into_owned();
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Done\"}}]}\n\ndata: [DONE]\n\n".into()
+        })
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "create", "--name", "Focus"])
+        .assert()
+        .success();
+    let mut ids = Vec::new();
+    for name in ["alpha.txt", "beta.txt", "gamma.txt"] {
+        let path = temp_dir.path().join(name);
+        fs::write(&path, format!("contents of {}", name)).unwrap();
+        let output = Command::cargo_bin("ola").unwrap()
+            .env("HOME", temp_dir.path())
+            .args(["project", "upload", "--project", "Focus", "--file"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let id = stdout.lines().find_map(|line| line.trim().strip_prefix("File ID: ")).unwrap();
+        ids.push(id.to_string());
+    }
+
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["project", "run", "--project", "Focus", "--goals", "Compare", "--file", &ids[0], "--files", &ids[2]])
+        .assert()
+        .success();
+    mock.assert();
+    let body = sent.lock().unwrap().clone();
+    assert!(body.contains("contents of alpha.txt"));
+    assert!(body.contains("contents of gamma.txt"));
+    assert!(!body.contains("beta.txt"));
+
+    // Ids that aren't in the project are rejected before anything is sent
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["project", "run", "--project", "Focus", "--goals", "Compare", "--files", &format!("{},missing-id", ids[1])])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("File ID 'missing-id' not found in project 'Focus'"));
+}
+
+#[test]
+fn test_project_run_request_size_guard_names_largest_file() {
+    let mut server = Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Done\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+    let settings_path = temp_dir.path().join(".ola/settings.yaml");
+    let settings = fs::read_to_string(&settings_path).unwrap();
+    fs::write(&settings_path, format!("{}  max_request_bytes: 4000\n", settings)).unwrap();
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "create", "--name", "Heavy"])
+        .assert()
+        .success();
+    for (name, size) in [("notes.txt", 200), ("dump.log", 6000)] {
+        let path = temp_dir.path().join(name);
+        fs::write(&path, "x".repeat(size)).unwrap();
+        Command::cargo_bin("ola").unwrap()
+            .env("HOME", temp_dir.path())
+            .args(["project", "upload", "--project", "Heavy", "--file"])
+            .arg(&path)
+            .assert()
+            .success();
+    }
+
+    // With --fail-fast nothing is sent
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "run", "--project", "Heavy", "--goals", "Summarize", "--fail-fast"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("over the 4000-byte max_request_bytes"), "stderr: {}", stderr);
+    assert!(stderr.contains("the largest part is file dump.log (6000 bytes)"), "stderr: {}", stderr);
+
+    // Otherwise it is a warning and the request goes ahead
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "run", "--project", "Heavy", "--goals", "Summarize"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning: request body is about"));
+    mock.assert();
+}
+
+#[test]
+fn test_project_run_records_access_for_sorting() {
+    let mut server = Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Done\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+    for name in ["Older", "Newer"] {
+        Command::cargo_bin("ola").unwrap()
+            .env("HOME", temp_dir.path())
+            .args(["project", "create", "--name", name])
+            .assert()
+            .success();
+    }
+    let projects_dir = temp_dir.path().join(".ola").join("data").join("projects");
+    let older_file = fs::read_dir(&projects_dir).unwrap()
+        .map(|entry| entry.unwrap().path().join("project.json"))
+        .find(|path| fs::read_to_string(path).is_ok_and(|content| content.contains("\"Older\"")))
+        .unwrap();
+    let before: serde_json::Value = serde_json::from_str(&fs::read_to_string(&older_file).unwrap()).unwrap();
+    assert!(before["last_accessed_at"].is_null());
+
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["project", "run", "--project", "Older", "--goals", "Summarize"])
+        .assert()
+        .success();
+    mock.assert();
+
+    // Running records the access time without counting as a change
+    let after: serde_json::Value = serde_json::from_str(&fs::read_to_string(&older_file).unwrap()).unwrap();
+    assert!(after["last_accessed_at"].is_string());
+    assert_eq!(after["updated_at"], before["updated_at"]);
+
+    let list = |sort: Option<&str>| {
+        let mut cmd = Command::cargo_bin("ola").unwrap();
+        cmd.env("HOME", temp_dir.path()).args(["project", "list"]);
+        if let Some(sort) = sort {
+            cmd.args(["--sort", sort]);
+        }
+        let stdout = String::from_utf8_lossy(&cmd.output().unwrap().stdout).to_string();
+        (stdout.find(" - Older").unwrap(), stdout.find(" - Newer").unwrap())
+    };
+    let (older, newer) = list(None);
+    assert!(newer < older);
+    let (older, newer) = list(Some("accessed"));
+    assert!(older < newer);
+}
+
+#[test]
+fn test_dry_run_prints_assembled_prompt_without_sending() {
+    // The provider is unreachable, so any request would fail the run
+    let temp_dir = TestHome::openai("http://127.0.0.1:1").create();
+    fs::write(temp_dir.path().join(".olaHints"), "Prefer plain words.\n").unwrap();
+    let notes = temp_dir.path().join("notes.txt");
+    fs::write(&notes, "Launch is on Friday\n").unwrap();
+
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "create", "--name", "Launch"])
+        .assert()
+        .success();
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .args(["project", "upload", "--project", "Launch", "--file"])
+        .arg(&notes)
+        .assert()
+        .success();
+
+    let projects_dir = temp_dir.path().join(".ola").join("data").join("projects");
+    let project_files = || {
+        let mut files: Vec<String> = fs::read_dir(&projects_dir).unwrap()
+            .filter_map(|entry| fs::read_to_string(entry.unwrap().path().join("project.json")).ok())
+            .collect();
+        files.sort();
+        files
+    };
+    let before = project_files();
+
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["project", "run", "--project", "Launch", "--goals", "Summarize the plan", "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Summarize the plan"));
+    assert!(stdout.contains("Launch is on Friday"));
+    assert!(stdout.contains("Prefer plain words."));
+    // Nothing is written, not even the project's last access time
+    assert!(!before.is_empty());
+    assert_eq!(project_files(), before);
+
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Name the day", "--format", "text", "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Name the day\ntext\n"));
+    assert!(stdout.contains("Prefer plain words."));
+}
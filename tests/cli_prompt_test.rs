@@ -3,6 +3,11 @@ use predicates::prelude::*;
 use std::process::Command as StdCommand;
 use tempfile::tempdir;
 
+#[path = "common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+use common::TestHome;
+
 #[test]
 fn test_prompt_help() {
     // Test help text for the prompt command
@@ -149,4 +154,1290 @@ fn test_prompt_no_thinking_flag() {
         .success();
     
     // We'd need to mock the API responses to verify this works correctly
-}
\ No newline at end of file
+}
+#[test]
+fn test_prompt_format_and_format_file_conflict() {
+    let temp_dir = tempdir().unwrap();
+    let format_path = temp_dir.path().join("format.txt");
+    std::fs::write(&format_path, "json").unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.arg("prompt")
+        .args(["--goals", "test", "--format", "text"])
+        .arg("--format-file")
+        .arg(&format_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_prompt_missing_warnings_file() {
+    let temp_dir = tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .arg("prompt")
+        .args(["--goals", "test"])
+        .arg("--warnings-file")
+        .arg(temp_dir.path().join("missing.txt"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read"));
+}
+
+#[test]
+fn test_prompt_quiet_hides_status_output() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Quiet answer\"}}]}\n\ndata: [DONE]\n\n")
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("Quiet answer"));
+    assert!(!stdout.contains("Sending"));
+    assert!(!stderr.contains("Sending"));
+    mock.assert();
+}
+
+#[test]
+fn test_request_status_stays_off_stdout() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Only this\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    // With --quiet, stdout is the response and nothing else
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "Only this");
+
+    // Without it, the request status goes to stderr
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["non-think", "--prompt", "Say hello"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("Only this"));
+    for status in ["Using model", "Sending prompt to OpenAI", "Response received"] {
+        assert!(!stdout.contains(status), "{} on stdout", status);
+        assert!(stderr.contains(status), "{} missing from stderr", status);
+    }
+    mock.assert();
+}
+
+#[test]
+fn test_prompt_lang_flag_adds_language_instruction() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("Respond in French\\.".to_string()))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Bonjour\"}}]}\n\ndata: [DONE]\n\n")
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--lang", "fr"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Bonjour"));
+    mock.assert();
+}
+
+#[test]
+fn test_prompt_explain_errors_sends_failure_to_helper() {
+    let mut primary = mockito::Server::new();
+    let primary_mock = primary.mock("POST", "/v1/chat/completions")
+        .with_status(400)
+        .with_body("{\"error\":{\"message\":\"The model `gpt-nope` does not exist\"}}")
+        .create();
+
+    let mut helper = mockito::Server::new();
+    let helper_mock = helper.mock("POST", "/api/generate")
+        .match_body(mockito::Matcher::Regex("gpt-nope` does not exist".to_string()))
+        .with_body("{\"response\":\"The configured model name is wrong.\",\"done\":true}\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::with_provider("OpenAI", Some("gpt-nope"), &primary.url())
+        .provider("Ollama", Some("llama3"), &helper.url())
+        .settings("error_helper:\n  provider: \"Ollama\"\n")
+        .create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--explain-errors"])
+        .assert()
+        .stdout(predicate::str::contains("The configured model name is wrong."));
+    primary_mock.assert();
+    helper_mock.assert();
+}
+
+#[test]
+fn test_log_raw_response_keeps_stripped_thinking() {
+    let mut server = mockito::Server::new();
+    let _mock = server.mock("POST", "/api/generate")
+        .with_body("{\"response\":\"<think>secret reasoning</think>Visible answer\",\"done\":true}\n")
+        .create();
+
+    let temp_dir = tempdir().unwrap();
+    let log_path = temp_dir.path().join("sessions.jsonl");
+    TestHome::with_provider("Ollama", Some("deepseek-r1"), &server.url())
+        .behavior("enable_logging: true")
+        .behavior("log_raw_response: true")
+        .behavior(&format!("log_file: \"{}\"", log_path.display()))
+        .write(temp_dir.path());
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Think it over", "--quiet", "--no-thinking"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Visible answer"));
+    assert!(!stdout.contains("secret reasoning"));
+
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+    assert!(entry["raw_response"].as_str().unwrap().contains("<think>secret reasoning</think>"));
+}
+
+#[test]
+fn test_no_thinking_output_matches_what_is_kept() {
+    let mut server = mockito::Server::new();
+    // An OpenAI-compatible endpoint serving a model that emits <think> blocks
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_body("{\"choices\":[{\"message\":{\"content\":\"<think>secret reasoning</think>Visible answer\"},\"finish_reason\":\"stop\"}]}")
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Think it over", "--quiet", "--no-thinking"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Visible answer"))
+        .stdout(predicate::str::contains("secret reasoning").not());
+    mock.assert();
+}
+
+#[test]
+fn test_recursion_stops_when_waves_converge() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Same answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--recursion", "3", "--converge", "0.95"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Converged after 2 waves"));
+    mock.assert();
+}
+
+// Run a quiet prompt against a mocked provider and return its stderr
+fn run_prompt_against(provider: &str, model: &str, path: &str, body: &str) -> String {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", path)
+        .match_query(mockito::Matcher::Any)
+        .with_body(body)
+        .create();
+
+    let temp_dir = TestHome::with_provider(provider, Some(model), &server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Write a long story", "--quiet"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    mock.assert();
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[test]
+fn test_json_repair_prints_repaired_json_once() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"choices":[{"message":{"content":"{\"colors\": [\"red\", \"green\",]}"},"finish_reason":"stop"}]}"#)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    // The raw response isn't streamed first, and the banner stays off stdout
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "List two colors", "--format", "json", "--json-repair"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("\"green\",]"), "{}", stdout);
+    assert!(!stdout.contains("Repaired JSON"));
+    assert_eq!(stdout.matches("\"colors\"").count(), 1);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("🔧 Repaired JSON:"));
+    mock.assert();
+}
+
+#[test]
+fn test_truncation_warning_openai() {
+    let stderr = run_prompt_against(
+        "OpenAI",
+        "gpt-4o",
+        "/v1/chat/completions",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"Once upon\"},\"finish_reason\":null}]}\n\ndata: {\"choices\":[{\"delta\":{},\"finish_reason\":\"length\"}]}\n\ndata: [DONE]\n\n",
+    );
+    assert!(stderr.contains("Response truncated at max_tokens"));    assert!(stderr.contains("raise --max-tokens"));
+}
+
+#[test]
+fn test_truncation_warning_anthropic() {
+    let stderr = run_prompt_against(
+        "Anthropic",
+        "claude-3-haiku-20240307",
+        "/v1/messages",
+        "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Once upon\"}}\n\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"max_tokens\"}}\n\n",
+    );
+    assert!(stderr.contains("Response truncated at max_tokens"));
+}
+
+#[test]
+fn test_extended_thinking_warns_when_raising_max_tokens() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/messages")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "max_tokens": 2048 })))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Noon\"}}\n\n")
+        .create();
+    let temp_dir = TestHome::with_provider("Anthropic", Some("claude-sonnet-4-20250514"), &server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "When is high tide?", "--quiet", "--extended-thinking", "--max-tokens", "1000"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("max_tokens 1000 is too small for extended thinking"));
+    mock.assert();
+}
+
+#[test]
+fn test_truncation_warning_gemini() {
+    let stderr = run_prompt_against(
+        "Gemini",
+        "gemini-1.5-flash",
+        "/v1beta/models/gemini-1.5-flash:generateContent",
+        "{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Once upon\"}]},\"finishReason\":\"MAX_TOKENS\"}]}",
+    );
+    assert!(stderr.contains("Response truncated at max_tokens"));
+}
+
+#[test]
+fn test_truncation_warning_ollama() {
+    let stderr = run_prompt_against(
+        "Ollama",
+        "llama3",
+        "/api/generate",
+        "{\"response\":\"Once upon\",\"done\":true,\"done_reason\":\"length\"}\n",
+    );
+    assert!(stderr.contains("Response truncated at max_tokens"));
+
+    let stderr = run_prompt_against(
+        "Ollama",
+        "llama3",
+        "/api/generate",
+        "{\"response\":\"The end.\",\"done\":true,\"done_reason\":\"stop\"}\n",
+    );
+    assert!(!stderr.contains("truncated"));
+}
+
+#[test]
+fn test_context_cmd_output_included_in_prompt() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("Output of `echo hello`:\\\\nhello".to_string()))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Got it\"}}]}\n\ndata: [DONE]\n\n")
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Summarize", "--quiet", "--context-cmd", "echo hello"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Got it"));
+    mock.assert();
+}
+
+#[test]
+fn test_context_label_titles_matching_context_block() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::Regex("### Context: build log\\\\nerror: linker failed".to_string()),
+            // Sources past the given labels keep their default heading
+            mockito::Matcher::Regex("Output of `echo hello`:\\\\nhello".to_string()),
+        ]))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Got it\"}}]}\n\ndata: [DONE]\n\n")
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Explain the failure", "--quiet", "--pipe", "--context-label", "build log", "--context-cmd", "echo hello"])
+        .write_stdin("error: linker failed")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Got it"));
+    mock.assert();
+}
+
+#[test]
+fn test_context_cmd_failure_is_reported() {
+    let temp_dir = tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Summarize", "--quiet", "--context-cmd", "echo broken >&2; exit 3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Context command failed").and(predicate::str::contains("broken")));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_context_cmd_killed_after_command_timeout() {
+    let temp_dir = TestHome::new().behavior("command_timeout_secs: 1").create();
+
+    let started = std::time::Instant::now();
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Summarize", "--quiet", "--context-cmd", "sleep 30"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("`sleep 30` timed out after 1s and was killed"));
+    assert!(started.elapsed() < std::time::Duration::from_secs(15));
+}
+
+#[test]
+fn test_recursion_aborts_when_a_wave_fails() {
+    let mut server = mockito::Server::new();
+    // The first run and wave 1 succeed, wave 2 gets a non-retryable error
+    let success = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Wave answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+    let failure = server.mock("POST", "/v1/chat/completions")
+        .with_status(400)
+        .with_body("{\"error\":{\"message\":\"bad request\"}}")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--recursion", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Recursion wave 2 failed").and(predicate::str::contains("Aborting recursion")));
+
+    // Wave 3 never ran, so neither mock saw an extra request
+    success.assert();
+    failure.assert();
+}
+
+#[test]
+fn test_provider_and_model_carry_over_to_recursion_waves() {
+    let mut server = mockito::Server::new();
+    // The first run and both waves go to the chosen provider and model, never the
+    // unreachable active one
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({"model": "gpt-4o-mini"})))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Wave answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(3)
+        .create();
+
+    let temp_dir = TestHome::with_provider("Ollama", Some("llama3"), "http://127.0.0.1:1")
+        .provider("OpenAI", Some("gpt-4o"), &server.url())
+        .create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--provider", "OpenAI", "--model", "gpt-4o-mini", "--recursion", "2"])
+        .assert()
+        .success();
+    mock.assert();
+}
+
+#[test]
+fn test_unknown_provider_lists_configured_ones() {
+    let temp_dir = TestHome::with_provider("Ollama", Some("llama3"), "http://127.0.0.1:1")
+        .provider("OpenAI", Some("gpt-4o"), "http://127.0.0.1:1")
+        .create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--provider", "Mistral"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Provider 'Mistral' is not configured. Configured providers: Ollama, OpenAI"))
+        .stderr(predicate::str::contains("No active provider configured").not());
+}
+
+#[test]
+fn test_collapse_whitespace_in_piped_context() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("first line second line third line".to_string()))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Collapsed\"}}]}\n\ndata: [DONE]\n\n")
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Summarize", "--quiet", "--pipe", "--collapse-whitespace"])
+        .write_stdin("first line\n\n  second\tline\nthird line\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Collapsed"));
+    mock.assert();
+}
+
+#[test]
+fn test_prompt_benchmark_reports_latency_stats() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "application/json")
+        .with_body("{\"choices\":[{\"message\":{\"content\":\"Benchmark answer\"},\"finish_reason\":\"stop\"}]}")
+        .expect(2)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--benchmark", "2"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Benchmark summary (2 runs)"));
+    for stat in ["min:", "max:", "mean:", "p95:", "tokens/sec"] {
+        assert!(stdout.contains(stat), "missing {} in {}", stat, stdout);
+    }
+    assert!(!stdout.contains("Benchmark answer"));
+    mock.assert();
+}
+
+#[test]
+fn test_structured_hints_apply_as_defaults() {
+    let mut server = mockito::Server::new();
+    // Format and warnings come from the hints file; only the free-form note is appended
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::Regex("Explain tides\\\\nbullet list\\\\nAvoid jargon\\.".to_string()),
+            mockito::Matcher::Regex("HINTS: Keep it short\\.\"".to_string()),
+        ]))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"- Tides\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+    // An explicit --format still wins over the hints
+    let explicit = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::Regex("Explain tides\\\\njson\\\\nAvoid jargon\\.".to_string()),
+        ]))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"{}\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+    std::fs::write(
+        temp_dir.path().join(".olaHints"),
+        "Keep it short.\n[Goals]\nExplain tides\n[Return Format]\nbullet list\n[Warnings]\nAvoid jargon.\n",
+    ).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Explain tides", "--quiet"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- Tides"));
+    mock.assert();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Explain tides", "--quiet", "--format", "json"])
+        .assert()
+        .success();
+    explicit.assert();
+}
+
+#[test]
+fn test_fresh_ignores_hints_and_configured_defaults() {
+    let mut server = mockito::Server::new();
+    let sent = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+    let captured = std::sync::Arc::clone(&sent);
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body_from_request(move |request| {
+            *captured.lock().unwrap() = request.utf8_lossy_body().unwrap().into_owned();
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Tides\"}}]}\n\ndata: [DONE]\n\n".into()
+        })
+        .expect(2)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url())
+        .settings("defaults:\n  warnings: \"Cite sources.\"\n  temperature: 0.9\n")
+        .create();
+    std::fs::write(temp_dir.path().join(".olaHints"), "Keep it short.\n[Warnings]\nAvoid jargon.\n").unwrap();
+
+    let run = |extra: &[&str]| {
+        Command::cargo_bin("ola").unwrap()
+            .env("HOME", temp_dir.path())
+            .current_dir(temp_dir.path())
+            .args(["prompt", "--goals", "Explain tides", "--quiet"])
+            .args(extra)
+            .assert()
+            .success();
+        sent.lock().unwrap().clone()
+    };
+
+    let body = run(&[]);
+    assert!(body.contains("HINTS: Keep it short."));
+    assert!(body.contains("Avoid jargon."));
+    assert!(body.contains("\"temperature\":0.9"));
+
+    let body = run(&["--fresh"]);
+    assert!(!body.contains("HINTS:"));
+    assert!(!body.contains("Avoid jargon."));
+    assert!(!body.contains("Cite sources."));
+    assert!(!body.contains("\"temperature\""));
+    mock.assert();
+}
+
+#[test]
+fn test_answer_only_prints_just_the_answer() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "stream": false })))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"choices":[{"message":{"content":"<think>Tides follow the moon.</think>\n\nThe answer is 42."},"finish_reason":"stop"}]}"#)
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Explain tides", "--answer-only"])
+        .assert()
+        .success()
+        .stdout("The answer is 42.\n");
+    mock.assert();
+}
+
+#[test]
+fn test_prompt_without_goals_or_terminal_fails_instead_of_waiting() {
+    let temp_dir = TestHome::new().create();
+
+    let started = std::time::Instant::now();
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .write_stdin("")
+        .timeout(std::time::Duration::from_secs(20))
+        .args(["prompt", "--quiet"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No input provided; pass --goals or pipe content with --pipe"));
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+}
+
+#[test]
+fn test_repeat_until_stops_once_a_response_matches() {
+    let mut server = mockito::Server::new();
+    // The first attempt misses; the second is sent the first response and matches
+    let first = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"choices":[{"message":{"content":"Still working on it."},"finish_reason":"stop"}]}"#)
+        .expect(1)
+        .create();
+    let second = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("Previous response.*Still working on it".to_string()))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"choices":[{"message":{"content":"DONE: 42"},"finish_reason":"stop"}]}"#)
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Solve it", "--answer-only", "--repeat-until", "^DONE", "--max-repeats", "5"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("DONE: 42\n"))
+        .stderr(predicate::str::contains("Response matched '^DONE' after 2 attempts"));
+    first.assert();
+    second.assert();
+}
+
+#[test]
+fn test_iterations_send_previous_response_and_feedback() {
+    let mut server = mockito::Server::new();
+    // The second iteration is sent the first response along with the scripted feedback
+    let first = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"A long first draft.\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+    let second = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("A long first draft.*Feedback on iteration 1.*Make it shorter".to_string()))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Short draft.\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    // An empty line after the second iteration accepts it, so the third never runs
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Write a summary", "--quiet", "--iterations", "3"])
+        .write_stdin("Make it shorter\n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Iteration 2/3"))
+        .stdout(predicate::str::contains("Iteration 3/3").not());
+    first.assert();
+    second.assert();
+}
+
+#[test]
+fn test_provider_without_model_rejects_foreign_default_model() {
+    let mut server = mockito::Server::new();
+    let tags = server.mock("GET", "/api/tags")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"models":[{"name":"llama3:latest","size":4661224676},{"name":"phi3:mini","size":2176178913}]}"#)
+        .create();
+    let generate = server.mock("POST", "/api/generate").expect(0).create();
+
+    let temp_dir = TestHome::with_provider("Ollama", None, &server.url())
+        .settings("default_model: \"gpt-5\"\n")
+        .create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Explain tides", "--quiet"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("default_model 'gpt-5' is not one of its models (available: llama3:latest, phi3:mini)"))
+        .stderr(predicate::str::contains("ola configure --provider Ollama --model <MODEL>"));
+    tags.assert();
+    generate.assert();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_recursion_temp_files_removed_unless_kept() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // The previous response is handed to the next wave in a temp file when --converge is set
+
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Secret answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(4)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+    let tmp = temp_dir.path().join("tmp");
+    std::fs::create_dir_all(&tmp).unwrap();
+    let wave_files = || -> Vec<std::path::PathBuf> {
+        std::fs::read_dir(&tmp).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with("ola-wave-"))
+            .collect()
+    };
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("TMPDIR", &tmp)
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--recursion", "2", "--converge", "0.99"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Kept temp file").not());
+    assert!(wave_files().is_empty());
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("TMPDIR", &tmp)
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--recursion", "2", "--converge", "0.99", "--keep-tempfiles"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Kept temp file"));
+    let kept = wave_files();
+    assert_eq!(kept.len(), 1);
+    assert_eq!(std::fs::read_to_string(&kept[0]).unwrap(), "Secret answer");
+    assert_eq!(std::fs::metadata(&kept[0]).unwrap().permissions().mode() & 0o777, 0o600);
+    mock.assert();
+}
+
+#[test]
+fn test_ollama_num_ctx_warns_about_long_prompt() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/api/generate")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "options": { "num_ctx": 256 } })))
+        .with_body("{\"response\":\"Local answer\",\"done\":false}\n{\"response\":\"\",\"done\":true,\"done_reason\":\"stop\"}\n")
+        .expect(1)
+        .create();
+
+    // 4k characters is about 1k tokens, well past a 256-token num_ctx
+    let temp_dir = TestHome::with_provider("Ollama", Some("llama3"), &server.url())
+        .provider_setting("num_ctx: 256")
+        .create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", &"word ".repeat(800)])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Local answer"))
+        .stderr(predicate::str::contains("more than the 256-token context window of llama3"))
+        .stderr(predicate::str::contains("raise num_ctx"));
+    mock.assert();
+}
+
+#[test]
+fn test_context_window_check_warns_and_refuses() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Long answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+
+    // gpt-4 has an 8k-token window; 40k characters is about 10k tokens
+    let home = TestHome::with_provider("OpenAI", Some("gpt-4"), &server.url());
+    let temp_dir = home.create();
+    let long_goals = "word ".repeat(8_000);
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", &long_goals])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Long answer"))
+        .stderr(predicate::str::contains("more than the 8192-token context window of gpt-4"));
+
+    // With the check set to refuse, nothing is sent
+    home.behavior("context_window_check: refuse").write(temp_dir.path());
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", &long_goals])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not sending: prompt is about"));
+
+    // The flag overrides the setting
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--context-window-check", "off", "--goals", &long_goals])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("context window").not());
+    mock.assert();
+}
+
+#[test]
+fn test_append_stdin_combines_piped_context_with_typed_goals() {
+    // The goals prompt needs a terminal while stdin is a pipe, so run ola under `script`
+    if StdCommand::new("script").arg("--version").output().is_err() {
+        eprintln!("skipping: `script` is not available");
+        return;
+    }
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::Regex("Typed goals".to_string()),
+            mockito::Matcher::Regex("PIPED CONTEXT".to_string()),
+        ]))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Combined answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let ola = assert_cmd::cargo::cargo_bin("ola");
+    let pipeline = format!("printf 'PIPED CONTEXT' | '{}' prompt --quiet --append-stdin", ola.display());
+    let mut child = StdCommand::new("script")
+        .args(["-qec", &pipeline, "/dev/null"])
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Type the goals, then accept the default format and warnings
+    std::io::Write::write_all(child.stdin.as_mut().unwrap(), b"Typed goals\r\r\r").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Combined answer"));
+    mock.assert();
+}
+
+#[test]
+fn test_strict_json_format_fails_on_non_json_response() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Here is your data: name=ola\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", "Describe ola", "--format", "json", "--strict"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Response does not follow the requested format: response is not valid JSON"));
+
+    // Without --strict the response is kept and only a note is printed
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", "Describe ola", "--format", "json"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Response does not follow the requested format"));
+    mock.assert();
+}
+
+#[test]
+fn test_strict_json_format_accepts_fenced_json() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_body(r#"{"choices":[{"message":{"content":"```json\n{\"name\": \"ola\"}\n```"},"finish_reason":"stop"}]}"#)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    // The compliance note goes to stderr so piped output is only the answer
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Describe ola", "--format", "json", "--strict"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"name\": \"ola\""))
+        .stdout(predicate::str::contains("Response follows").not())
+        .stderr(predicate::str::contains("Response follows the json format"));
+    mock.assert();
+}
+
+#[test]
+fn test_no_animation_frames_when_stderr_is_not_a_tty() {
+    let mut server = mockito::Server::new();
+    let streamed = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({"stream": true})))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Plain answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+    // --no-thinking fetches the response whole so it can be filtered before printing
+    let whole = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({"stream": false})))
+        .with_body("{\"choices\":[{\"message\":{\"content\":\"Plain answer\"},\"finish_reason\":\"stop\"}]}")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    // stderr is captured through a pipe; status lines still appear, but never as \r frames
+    for args in [vec!["prompt", "--goals", "Say hello", "--recursion", "1"], vec!["prompt", "--goals", "Say hello", "--no-thinking"]] {
+        let output = Command::cargo_bin("ola").unwrap()
+            .env("HOME", temp_dir.path())
+            .current_dir(temp_dir.path())
+            .args(&args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!stderr.contains('\r'), "unexpected animation frame in {:?}", stderr);
+        assert!(stderr.contains("Prompt executed successfully"));
+    }
+    streamed.assert();
+    whole.assert();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_attach_clipboard_adds_clipboard_as_context() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("Clipboard contents:\\\\nCOPIED TEXT".to_string()))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Clipboard answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    // A stub xclip stands in for the system clipboard
+    let bin_dir = temp_dir.path().join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let xclip = bin_dir.join("xclip");
+    std::fs::write(&xclip, "#!/bin/sh\nprintf 'COPIED TEXT'\n").unwrap();
+    std::fs::set_permissions(&xclip, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("PATH", &bin_dir)
+        .env_remove("WAYLAND_DISPLAY")
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", "Summarize this", "--attach-clipboard"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Clipboard answer"));
+    mock.assert();
+
+    // Without any clipboard tool the prompt fails before sending
+    std::fs::remove_file(&xclip).unwrap();
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("PATH", &bin_dir)
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", "Summarize this", "--attach-clipboard"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No clipboard tool found"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_clipboard_only_quiet_copies_without_output() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "application/json")
+        .with_body("{\"choices\":[{\"message\":{\"content\":\"Copied answer\"},\"finish_reason\":\"stop\"}]}")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    // A stub xclip records what would have been copied
+    let bin_dir = temp_dir.path().join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let copied = temp_dir.path().join("copied.txt");
+    let xclip = bin_dir.join("xclip");
+    std::fs::write(&xclip, format!("#!/bin/sh\n/bin/cat > '{}'\n", copied.display())).unwrap();
+    std::fs::set_permissions(&xclip, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("PATH", &bin_dir)
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--clipboard-only", "--goals", "Write a haiku"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_empty());
+    mock.assert();
+    assert_eq!(std::fs::read_to_string(&copied).unwrap(), "Copied answer");
+}
+
+#[test]
+fn test_ollama_missing_model_suggests_pull() {
+    let mut server = mockito::Server::new();
+    let _generate = server.mock("POST", "/api/generate")
+        .with_status(404)
+        .with_body(r#"{"error":"model \"llama3\" not found, try pulling it first"}"#)
+        .create();
+
+    let temp_dir = TestHome::with_provider("Ollama", Some("llama3"), &server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", "Say hi"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Run 'ollama pull llama3'"))
+        .stderr(predicate::str::contains("--auto-pull"));
+}
+
+#[test]
+fn test_redact_scrubs_secrets_before_sending() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex(r"key \[REDACTED\] and host \[REDACTED\] end".to_string()))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Done\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args([
+            "prompt", "--quiet",
+            "--goals", "Check key sk-live-0123456789abcdefXYZ and host db.internal.example end",
+            "--redact", r"db\.internal\.\S+",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Redacted 2 secrets from the prompt"));
+    mock.assert();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_json_response_displayed_indented_but_copied_raw() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let raw = r#"{"name":"ola","tags":["cli","llm"]}"#;
+    let mut server = mockito::Server::new();
+    // The response is collected whole rather than streamed so it can be indented
+    let _mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({"stream": false})))
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({"choices": [{"message": {"content": raw}, "finish_reason": "stop"}]}).to_string())
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let bin_dir = temp_dir.path().join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let copied = temp_dir.path().join("copied.txt");
+    let xclip = bin_dir.join("xclip");
+    std::fs::write(&xclip, format!("#!/bin/sh\n/bin/cat > '{}'\n", copied.display())).unwrap();
+    std::fs::set_permissions(&xclip, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("PATH", &bin_dir)
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Describe ola", "--format", "json", "--clipboard"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("{\n  \"name\": \"ola\",\n  \"tags\": [\n    \"cli\","))
+        .stdout(predicate::str::contains(raw).not());
+    assert_eq!(std::fs::read_to_string(&copied).unwrap(), raw);
+
+    // --pretty-clipboard copies what was displayed
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("PATH", &bin_dir)
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Describe ola", "--format", "json", "--clipboard", "--pretty-clipboard"])
+        .assert()
+        .success();
+    assert!(std::fs::read_to_string(&copied).unwrap().starts_with("{\n  \"name\": \"ola\""));
+}
+
+#[test]
+fn test_piped_input_truncated_at_max_stdin_bytes() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex(r"0123456789abcdef\\n---".to_string()))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Done\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).behavior("max_stdin_bytes: 16").create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", "Summarize", "--append-stdin"])
+        .write_stdin(format!("0123456789abcdef{}", "x".repeat(100_000)))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("piped input exceeded max_stdin_bytes (16 bytes)"));
+    mock.assert();
+}
+
+#[test]
+fn test_output_file_receives_final_response() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Saved \"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"answer\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    // Parent directories are created as needed
+    let prompt_file = temp_dir.path().join("out/prompt/answer.txt");
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "-o"])
+        .arg(&prompt_file)
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(&prompt_file).unwrap(), "Saved answer\n");
+
+    let non_think_file = temp_dir.path().join("out/non-think/answer.txt");
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["non-think", "--prompt", "Say hello", "--quiet", "--output-file"])
+        .arg(&non_think_file)
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(&non_think_file).unwrap(), "Saved answer\n");
+    mock.assert();
+}
+
+#[test]
+fn test_temperature_and_max_tokens_flags_override_config() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "temperature": 0.2, "max_tokens": 900 })))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Cool\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url())
+        .provider_setting("temperature: 1.1")
+        .provider_setting("max_tokens: 4000")
+        .create();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--quiet", "--temperature", "0.2", "--max-tokens", "900"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cool"));
+    mock.assert();
+
+    // Out-of-range temperatures are rejected before anything is sent
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Say hello", "--temperature", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("temperature must be between 0.0 and 2.0"));
+}
+
+#[test]
+fn test_show_prompt_echoes_redacted_prompt_after_response() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Rotated\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(1)
+        .create();
+
+    let temp_dir = TestHome::openai(&server.url()).create();
+
+    let output = Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--quiet", "--goals", "Rotate token=secret123", "--redact", "secret\\d+", "--show-prompt"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "Rotated");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--- Prompt sent to gpt-4o ---"), "{}", stderr);
+    assert!(stderr.contains("Rotate token=[REDACTED]"), "{}", stderr);
+    assert!(!stderr.contains("secret123"));
+    mock.assert();
+}
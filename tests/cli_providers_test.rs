@@ -0,0 +1,86 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+// ola with HOME pointed at a temporary directory and no profile from the environment
+fn ola(home: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", home).env_remove("OLA_HOME").env_remove("OLA_PROFILE");
+    cmd
+}
+
+// Configure OpenAI (active) and Ollama
+fn write_two_providers(home: &Path) {
+    let ola_dir = home.join(".ola");
+    fs::create_dir_all(&ola_dir).unwrap();
+    fs::write(
+        ola_dir.join("settings.yaml"),
+        "active_provider: \"OpenAI\"\nproviders:\n  - provider: \"OpenAI\"\n    api_key: \"sk-test\"\n    model: \"gpt-4o\"\n    additional_settings: null\n  - provider: \"Ollama\"\n    api_key: \"\"\n    model: \"llama3\"\n    additional_settings: null\nbehavior:\n  enable_logging: false\n",
+    )
+    .unwrap();
+}
+
+// Saved settings file as YAML
+fn settings(home: &Path) -> serde_yaml::Value {
+    serde_yaml::from_str(&fs::read_to_string(home.join(".ola").join("settings.yaml")).unwrap()).unwrap()
+}
+
+#[test]
+fn test_providers_list_marks_active_provider() {
+    let temp_dir = tempdir().unwrap();
+    write_two_providers(temp_dir.path());
+
+    ola(temp_dir.path()).args(["providers", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("* OpenAI - gpt-4o"))
+        .stdout(predicate::str::contains("  Ollama - llama3"));
+}
+
+#[test]
+fn test_providers_use_switches_active_provider() {
+    let temp_dir = tempdir().unwrap();
+    let home = temp_dir.path();
+    write_two_providers(home);
+
+    ola(home).args(["providers", "use", "ollama"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Now using provider 'Ollama'"));
+    assert_eq!(settings(home)["active_provider"], "Ollama");
+    // Settings outside the provider list are kept
+    assert_eq!(settings(home)["behavior"]["enable_logging"], false);
+
+    ola(home).args(["providers", "use", "Gemini"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Provider 'Gemini' is not configured (configured providers: OpenAI, Ollama)"));
+    assert_eq!(settings(home)["active_provider"], "Ollama");
+}
+
+#[test]
+fn test_providers_remove_clears_active_provider() {
+    let temp_dir = tempdir().unwrap();
+    let home = temp_dir.path();
+    write_two_providers(home);
+
+    ola(home).args(["providers", "remove", "Ollama"]).assert().success();
+    let saved = settings(home);
+    assert_eq!(saved["active_provider"], "OpenAI");
+    assert_eq!(saved["providers"].as_sequence().unwrap().len(), 1);
+
+    ola(home).args(["providers", "remove", "OpenAI"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No provider is active now"));
+    let saved = settings(home);
+    assert_eq!(saved["active_provider"], "");
+    assert!(saved["providers"].as_sequence().unwrap().is_empty());
+
+    ola(home).args(["providers", "remove", "OpenAI"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("none are configured yet"));
+}
@@ -3,6 +3,11 @@ use predicates::prelude::*;
 use std::fs;
 use tempfile::tempdir;
 
+#[path = "common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+use common::TestHome;
+
 #[test]
 #[ignore]
 fn test_session_help() {
@@ -143,4 +148,61 @@ fn test_session_logs() {
     
     // Restore original directory
     std::env::set_current_dir(old_dir).unwrap();
-}
\ No newline at end of file
+}
+#[test]
+fn test_session_replay_resends_logged_parameters() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::Regex("Plan the release".to_string()),
+            mockito::Matcher::Regex("markdown".to_string()),
+            mockito::Matcher::Regex("No dates".to_string()),
+        ]))
+        .with_header("content-type", "text/event-stream")
+        .with_body("data: {\"choices\":[{\"delta\":{\"content\":\"Release plan\"}}]}\n\ndata: [DONE]\n\n")
+        .expect(2)
+        .create();
+
+    let temp_dir = tempdir().unwrap();
+    let log_path = temp_dir.path().join("sessions.jsonl");
+    TestHome::openai(&server.url())
+        .behavior("enable_logging: true")
+        .behavior(&format!("log_file: \"{}\"", log_path.display()))
+        .write(temp_dir.path());
+
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["prompt", "--goals", "Plan the release", "--format", "markdown", "--warnings", "No dates", "--quiet"])
+        .assert()
+        .success();
+    let log = fs::read_to_string(&log_path).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+    let id = entry["id"].as_str().unwrap().to_string();
+
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["session", "replay", "--id", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Release plan"))
+        .stderr(predicate::str::contains(format!("Replaying session {}", id)));
+    mock.assert();
+
+    // The replay is logged as a new session with its own id
+    let log = fs::read_to_string(&log_path).unwrap();
+    let ids: Vec<String> = log.lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(ids.len(), 2);
+    assert_ne!(ids[0], ids[1]);
+
+    Command::cargo_bin("ola").unwrap()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["session", "replay", "--id", "missing"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No session with id 'missing'"));
+}
@@ -5,6 +5,11 @@ use std::io::Write;
 use std::path::PathBuf;
 use tempfile::{tempdir, TempDir};
 
+#[path = "common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+use common::TestHome;
+
 // Helper function to create a temporary settings file
 fn setup_temp_settings_dir() -> TempDir {
     let temp_dir = tempdir().unwrap();
@@ -153,4 +158,303 @@ fn test_settings_reset() {
     if let Some(home) = old_home {
         std::env::set_var("HOME", home);
     }
-}
\ No newline at end of file
+}
+#[cfg(unix)]
+#[test]
+fn test_settings_edit_rejects_invalid_yaml() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempdir().unwrap();
+    let settings_dir = temp_dir.path().join(".ola");
+    fs::create_dir_all(&settings_dir).unwrap();
+    let settings_file = settings_dir.join("settings.yaml");
+    fs::write(&settings_file, "default_model: \"before_edit\"\n").unwrap();
+
+    // Fake editor that overwrites the file it is given with invalid YAML
+    let editor = temp_dir.path().join("fake-editor.sh");
+    fs::write(&editor, "#!/bin/sh\necho 'default_model: [unclosed' > \"$1\"\n").unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .args(["settings", "--edit"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Settings were not changed"));
+
+    assert_eq!(fs::read_to_string(&settings_file).unwrap(), "default_model: \"before_edit\"\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_settings_edit_fixes_broken_file_and_keeps_import_backup() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempdir().unwrap();
+    let settings_dir = temp_dir.path().join(".ola");
+    fs::create_dir_all(&settings_dir).unwrap();
+    let settings_file = settings_dir.join("settings.yaml");
+    fs::write(&settings_file, "default_model: [unclosed\n").unwrap();
+    let import_backup = settings_dir.join("settings.yaml.import.bak");
+    fs::write(&import_backup, "default_model: \"before_import\"\n").unwrap();
+
+    // Fake editor that replaces the broken file with valid YAML
+    let editor = temp_dir.path().join("fake-editor.sh");
+    fs::write(&editor, "#!/bin/sh\necho 'default_model: \"fixed\"' > \"$1\"\n").unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .args(["settings", "--edit"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Settings updated and validated"));
+
+    assert_eq!(fs::read_to_string(&settings_file).unwrap(), "default_model: \"fixed\"\n");
+    assert_eq!(fs::read_to_string(&import_backup).unwrap(), "default_model: \"before_import\"\n");
+    assert!(!settings_dir.join("settings.yaml.edit.bak").exists());
+}
+
+#[test]
+fn test_settings_export_import_round_trip() {
+    let source = tempdir().unwrap();
+    let source_dir = source.path().join(".ola");
+    fs::create_dir_all(&source_dir).unwrap();
+    fs::write(
+        source_dir.join("settings.yaml"),
+        "active_provider: \"OpenAI\"\nproviders:\n  - provider: \"OpenAI\"\n    api_key: \"sk-secret\"\n    model: \"gpt-4o\"\n    additional_settings: null\ndefault_model: \"shared_model\"\ndefaults:\n  return_format: \"json\"\n  warnings: \"Cite sources.\"\nbehavior:\n  enable_logging: false\n  log_file: \"team.log\"\n",
+    )
+    .unwrap();
+    let export_file = source.path().join("team-settings.yaml");
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", source.path())
+        .args(["settings", "export"])
+        .arg(&export_file)
+        .assert()
+        .success();
+
+    let exported = fs::read_to_string(&export_file).unwrap();
+    assert!(!exported.contains("sk-secret"));
+
+    // Import into a fresh HOME
+    let target = tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", target.path())
+        .args(["settings", "import"])
+        .arg(&export_file)
+        .assert()
+        .success();
+
+    let imported: serde_yaml::Value = serde_yaml::from_str(&fs::read_to_string(target.path().join(".ola/settings.yaml")).unwrap()).unwrap();
+    let original: serde_yaml::Value = serde_yaml::from_str(&exported).unwrap();
+    assert_eq!(imported, original);
+    assert_eq!(imported["default_model"], "shared_model");
+    assert_eq!(imported["defaults"]["return_format"], "json");
+    assert_eq!(imported["behavior"]["log_file"], "team.log");
+    assert_eq!(imported["active_provider"], "OpenAI");
+    assert_eq!(imported["providers"][0]["model"], "gpt-4o");
+}
+
+#[test]
+fn test_settings_import_keeps_existing_keys_and_backs_up() {
+    let temp_dir = tempdir().unwrap();
+    let settings_dir = temp_dir.path().join(".ola");
+    fs::create_dir_all(&settings_dir).unwrap();
+    let before = "active_provider: \"OpenAI\"\nproviders:\n  - provider: \"OpenAI\"\n    api_key: \"sk-mine\"\n    model: \"gpt-4\"\n    additional_settings: null\ndefault_model: \"old_model\"\n";
+    fs::write(settings_dir.join("settings.yaml"), before).unwrap();
+
+    let shared = temp_dir.path().join("shared.yaml");
+    fs::write(&shared, "active_provider: \"OpenAI\"\nproviders:\n  - provider: \"OpenAI\"\n    api_key: \"\"\n    model: \"gpt-4o\"\n    additional_settings: null\ndefault_model: \"team_model\"\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["settings", "import"])
+        .arg(&shared)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(settings_dir.join("settings.yaml.import.bak").display().to_string()));
+
+    let imported = fs::read_to_string(settings_dir.join("settings.yaml")).unwrap();
+    assert!(imported.contains("sk-mine"));
+    assert!(imported.contains("team_model"));
+    assert_eq!(fs::read_to_string(settings_dir.join("settings.yaml.import.bak")).unwrap(), before);
+
+    // Invalid files are rejected without touching the current settings
+    fs::write(&shared, "default_model: [unclosed").unwrap();
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["settings", "import"])
+        .arg(&shared)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Settings were not changed"));
+    assert_eq!(fs::read_to_string(settings_dir.join("settings.yaml")).unwrap(), imported);
+}
+
+#[test]
+fn test_emoji_disabled_uses_ascii_markers() {
+    let temp_dir = TestHome::new().behavior("emoji: false").create();
+    let export_file = temp_dir.path().join("exported.yaml");
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["settings", "export"])
+        .arg(&export_file)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[OK] Settings exported to"))
+        .stderr(predicate::str::contains("✓").not());
+
+    let broken = temp_dir.path().join("broken.yaml");
+    fs::write(&broken, "default_model: [unclosed").unwrap();
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["settings", "import"])
+        .arg(&broken)
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with("\x1b[31mError: Settings were not changed"));
+
+    // Without the setting, a terminal that can't render emoji is detected from TERM
+    TestHome::new().write(temp_dir.path());
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .env("TERM", "linux")
+        .args(["settings", "export"])
+        .arg(&export_file)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[OK] Settings exported to"));
+}
+
+#[test]
+fn test_malformed_settings_names_the_file_and_is_not_overwritten() {
+    let temp_dir = tempdir().unwrap();
+    let settings_dir = temp_dir.path().join(".ola");
+    fs::create_dir_all(&settings_dir).unwrap();
+    let settings_file = settings_dir.join("settings.yaml");
+    let broken = "active_provider: \"OpenAI\"\nbehavior:\n  enable_logging: [false\n";
+    fs::write(&settings_file, broken).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["settings", "--default-model", "llama3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!("{} is not valid YAML", settings_file.display())))
+        .stderr(predicate::str::contains("line 3"))
+        .stderr(predicate::str::contains("--repair"));
+    assert_eq!(fs::read_to_string(&settings_file).unwrap(), broken);
+
+    // --repair backs up the broken file and starts over from the defaults
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["settings", "--view", "--repair"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("settings.yaml.bak"));
+    assert_eq!(fs::read_to_string(settings_dir.join("settings.yaml.bak")).unwrap(), broken);
+    assert!(fs::read_to_string(&settings_file).unwrap().contains("default_model"));
+}
+
+#[test]
+fn test_config_migrate_moves_json_config_into_yaml() {
+    let temp_dir = tempdir().unwrap();
+    let settings_dir = temp_dir.path().join(".ola");
+    fs::create_dir_all(&settings_dir).unwrap();
+    let json_file = settings_dir.join("config.json");
+    let legacy = r#"{
+  "active_provider": "Anthropic",
+  "providers": [
+    {"provider": "OpenAI", "api_key": "sk-openai", "model": "gpt-4o", "additional_settings": null},
+    {"provider": "Anthropic", "api_key": "sk-ant-key", "model": "claude-sonnet-4-20250514", "additional_settings": {"max_tokens": 4096}}
+  ]
+}"#;
+    fs::write(&json_file, legacy).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["config", "migrate"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("config.json.bak"));
+
+    assert!(!json_file.exists());
+    assert_eq!(fs::read_to_string(settings_dir.join("config.json.bak")).unwrap(), legacy);
+    let migrated: serde_yaml::Value = serde_yaml::from_str(&fs::read_to_string(settings_dir.join("settings.yaml")).unwrap()).unwrap();
+    let expected: serde_yaml::Value = serde_json::from_str(legacy).unwrap();
+    assert_eq!(migrated["active_provider"], expected["active_provider"]);
+    assert_eq!(migrated["providers"], expected["providers"]);
+
+    // Running it again has nothing left to do
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["config", "migrate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No config.json to migrate"));
+}
+
+#[test]
+fn test_settings_view_falls_back_to_defaults_for_malformed_file() {
+    let temp_dir = tempdir().unwrap();
+    let settings_dir = temp_dir.path().join(".ola");
+    fs::create_dir_all(&settings_dir).unwrap();
+    let settings_file = settings_dir.join("settings.yaml");
+    let broken = "active_provider: \"OpenAI\"\nbehavior:\n  enable_logging: [false\n";
+    fs::write(&settings_file, broken).unwrap();
+
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    cmd.env("HOME", temp_dir.path())
+        .args(["settings", "--view"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Current settings:"))
+        .stdout(predicate::str::contains("default_model: gpt-5"))
+        .stderr(predicate::str::contains("(showing defaults; your settings file failed to load:"))
+        .stderr(predicate::str::contains("is not valid YAML"));
+    // Viewing never touches the broken file
+    assert_eq!(fs::read_to_string(&settings_file).unwrap(), broken);
+}
+
+#[test]
+fn test_theme_setting_styles_command_output() {
+    // mono: no ANSI escape codes at all
+    let temp_dir = TestHome::new().behavior("theme: mono").create();
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .args(["models", "--provider", "OpenAI"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("OpenAI Models"));
+    assert!(!stdout.contains('\x1b'));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains('\x1b'));
+
+    // minimal: banners without emoji
+    TestHome::new().behavior("theme: minimal").write(temp_dir.path());
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .args(["models", "--provider", "OpenAI"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("║  OpenAI Models  ║"));
+    assert!(!stdout.contains('🧠'));
+
+    // ocean (the default) keeps colors and emoji
+    TestHome::new().write(temp_dir.path());
+    let mut cmd = Command::cargo_bin("ola").unwrap();
+    let output = cmd.env("HOME", temp_dir.path())
+        .args(["models", "--provider", "OpenAI"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("🧠 OpenAI Models 🧠"));
+    assert!(stdout.contains('\x1b'));
+}
@@ -1,7 +1,7 @@
 use mockito::{Mock, Server};
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::Path;
 use tempfile::{tempdir, TempDir};
 
 // Common test utilities
@@ -66,7 +66,7 @@ pub fn setup_temp_config(provider: &str) -> TempDir {
     
     // Create config file based on provider
     let config_file = config_dir.join("config.yaml");
-    let mut server = Server::new();
+    let server = Server::new();
     let server_url = server.url();
     let config_content = match provider {
         "OpenAI" => format!(r#"
@@ -129,4 +129,107 @@ behavior:
     file.write_all(settings_content.as_bytes()).unwrap();
     
     temp_dir
-}
\ No newline at end of file
+}
+/// A HOME for CLI tests whose `.ola/settings.yaml` configures providers at mock servers.
+/// The first provider is active and logging is off unless a behavior setting says otherwise.
+pub struct TestHome {
+    providers: Vec<TestProvider>,
+    behavior: Vec<String>,
+    settings: String,
+}
+
+struct TestProvider {
+    name: String,
+    model: Option<String>,
+    base_url: String,
+    additional_settings: Vec<String>,
+}
+
+impl TestHome {
+    /// A settings file with no providers, for tests that only need behavior settings
+    pub fn new() -> Self {
+        Self { providers: Vec::new(), behavior: vec!["enable_logging: false".to_string()], settings: String::new() }
+    }
+
+    /// Make `provider` the active provider, talking to `base_url` with `model` (or none)
+    pub fn with_provider(provider: &str, model: Option<&str>, base_url: &str) -> Self {
+        Self::new().provider(provider, model, base_url)
+    }
+
+    /// The most common setup: OpenAI with gpt-4o as the active provider
+    pub fn openai(base_url: &str) -> Self {
+        Self::with_provider("OpenAI", Some("gpt-4o"), base_url)
+    }
+
+    /// Configure another provider after the active one
+    pub fn provider(mut self, provider: &str, model: Option<&str>, base_url: &str) -> Self {
+        self.providers.push(TestProvider {
+            name: provider.to_string(),
+            model: model.map(str::to_string),
+            base_url: base_url.to_string(),
+            additional_settings: Vec::new(),
+        });
+        self
+    }
+
+    /// Add a line such as `num_ctx: 256` to the last provider's additional_settings
+    pub fn provider_setting(mut self, setting: &str) -> Self {
+        if let Some(provider) = self.providers.last_mut() {
+            provider.additional_settings.push(setting.to_string());
+        }
+        self
+    }
+
+    /// Set a behavior setting such as `max_stdin_bytes: 16`, replacing one with the same key
+    pub fn behavior(mut self, setting: &str) -> Self {
+        let key = setting.split(':').next().unwrap_or(setting);
+        self.behavior.retain(|line| line.split(':').next() != Some(key));
+        self.behavior.push(setting.to_string());
+        self
+    }
+
+    /// Append top-level YAML, such as a `defaults:` block
+    pub fn settings(mut self, yaml: &str) -> Self {
+        self.settings.push_str(yaml);
+        self
+    }
+
+    /// The settings file contents
+    pub fn yaml(&self) -> String {
+        let mut yaml = String::new();
+        if let Some(active) = self.providers.first() {
+            yaml.push_str(&format!("active_provider: \"{}\"\nproviders:\n", active.name));
+        }
+        for provider in &self.providers {
+            let api_key = if provider.name == "Ollama" { "" } else { "test_key" };
+            let model = provider.model.as_ref().map(|m| format!("\"{}\"", m)).unwrap_or_else(|| "null".to_string());
+            yaml.push_str(&format!(
+                "  - provider: \"{}\"\n    api_key: \"{}\"\n    model: {}\n    additional_settings:\n      base_url: \"{}\"\n",
+                provider.name, api_key, model, provider.base_url
+            ));
+            for setting in &provider.additional_settings {
+                yaml.push_str(&format!("      {}\n", setting));
+            }
+        }
+        yaml.push_str("behavior:\n");
+        for setting in &self.behavior {
+            yaml.push_str(&format!("  {}\n", setting));
+        }
+        yaml.push_str(&self.settings);
+        yaml
+    }
+
+    /// Write the settings file under `home`, replacing any earlier one
+    pub fn write(&self, home: &Path) {
+        let config_dir = home.join(".ola");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("settings.yaml"), self.yaml()).unwrap();
+    }
+
+    /// Write the settings file into a new temporary HOME
+    pub fn create(&self) -> TempDir {
+        let temp_dir = tempdir().unwrap();
+        self.write(temp_dir.path());
+        temp_dir
+    }
+}
@@ -0,0 +1,41 @@
+#![cfg(unix)]
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::tempdir;
+use ola::settings::NvimSettings;
+use ola::utils::editor;
+
+// Editor resolution and editing share process-wide environment variables, so they run in one test
+#[test]
+fn test_editor_resolution_and_round_trip() {
+    let temp_dir = tempdir().unwrap();
+    env::remove_var("VISUAL");
+    env::remove_var("EDITOR");
+
+    // Without $EDITOR/$VISUAL, the nvim setting is used when enabled, otherwise vi
+    let mut nvim = NvimSettings::default();
+    assert_eq!(editor::editor_command(&nvim), ("vi".to_string(), Vec::new()));
+    nvim.enabled = true;
+    nvim.args = vec!["-u".to_string(), "NONE".to_string()];
+    assert_eq!(editor::editor_command(&nvim), ("nvim".to_string(), nvim.args.clone()));
+
+    // $VISUAL beats the nvim setting, and $EDITOR beats $VISUAL
+    env::set_var("VISUAL", "code --wait");
+    assert_eq!(editor::editor_command(&nvim), ("code".to_string(), vec!["--wait".to_string()]));
+    env::set_var("EDITOR", "nano");
+    assert_eq!(editor::editor_command(&nvim), ("nano".to_string(), Vec::new()));
+    env::remove_var("VISUAL");
+    env::remove_var("EDITOR");
+
+    // A fake editor that appends a line; the original content must survive the round trip
+    let fake_editor = temp_dir.path().join("fake-editor.sh");
+    fs::write(&fake_editor, "#!/bin/sh\necho 'added by editor' >> \"$1\"\n").unwrap();
+    fs::set_permissions(&fake_editor, fs::Permissions::from_mode(0o755)).unwrap();
+    let edited = editor::open_in_editor("original content\n", fake_editor.to_str().unwrap(), &[]).unwrap();
+    assert_eq!(edited, "original content\nadded by editor\n");
+
+    // A failing editor is reported as an error
+    assert!(editor::open_in_editor("original content\n", "false", &[]).is_err());
+}
@@ -0,0 +1,54 @@
+use ola::utils::format_check::{self, check_format};
+use ola::utils::output;
+
+#[test]
+fn test_json_format() {
+    assert_eq!(check_format("json", "{\"ok\": true}"), Some(Ok(())));
+    assert_eq!(check_format("JSON", "<think>plan</think>\n[1, 2]"), Some(Ok(())));
+    let error = check_format("json", "Sure! {\"ok\": true}").unwrap().unwrap_err();
+    assert!(error.contains("not valid JSON"));
+
+    // A fenced answer is still valid JSON, with or without a language tag
+    assert_eq!(check_format("json", "```json\n{\"ok\": true}\n```"), Some(Ok(())));
+    assert_eq!(check_format("json", "<think>plan</think>\n```\n[1, 2]\n```\n"), Some(Ok(())));
+    assert!(check_format("json", "```json\n{\"ok\": }\n```").unwrap().is_err());
+}
+
+#[test]
+fn test_json_shape_format() {
+    let shape = r#"{"name": "string", "tags": ["string"], "stars": 0}"#;
+    assert_eq!(check_format(shape, r#"{"name": "ola", "tags": ["cli"], "stars": 42, "extra": null}"#), Some(Ok(())));
+
+    let missing = check_format(shape, r#"{"name": "ola", "stars": 42}"#).unwrap().unwrap_err();
+    assert_eq!(missing, "response.tags is missing");
+    let wrong_type = check_format(shape, r#"{"name": "ola", "tags": ["cli", 3], "stars": 42}"#).unwrap().unwrap_err();
+    assert_eq!(wrong_type, "response.tags[1] should be a string, found a number");
+    assert_eq!(check_format(shape, "```json\n{\"name\": \"ola\", \"tags\": [], \"stars\": 1}\n```"), Some(Ok(())));
+}
+
+#[test]
+fn test_markdown_and_unchecked_formats() {
+    assert_eq!(check_format("markdown", "# Title\n\n- one\n- two"), Some(Ok(())));
+    assert!(check_format("md", "Just a plain sentence.").unwrap().is_err());
+    assert_eq!(check_format("text", "anything"), None);
+    assert_eq!(check_format("bullet points", "anything"), None);
+}
+
+#[test]
+fn test_json_formats_and_indentation() {
+    assert!(format_check::is_json_format(" JSON "));
+    assert!(format_check::is_json_format(r#"{"name": "string"}"#));
+    assert!(!format_check::is_json_format("markdown"));
+
+    // Key order and number formatting are kept; empty containers stay inline
+    let pretty = output::format_json(r#"{"name":"ola","tags":["a","b, c"],"meta":{},"score":1.50,"ok":true}"#, false).unwrap();
+    assert_eq!(pretty, "{\n  \"name\": \"ola\",\n  \"tags\": [\n    \"a\",\n    \"b, c\"\n  ],\n  \"meta\": {},\n  \"score\": 1.50,\n  \"ok\": true\n}");
+
+    // Escaped quotes don't end a string early
+    let pretty = output::format_json(r#"[{"quote":"say \"hi\""}]"#, false).unwrap();
+    assert_eq!(pretty, "[\n  {\n    \"quote\": \"say \\\"hi\\\"\"\n  }\n]");
+
+    // Invalid JSON and bare scalars are left to the caller
+    assert_eq!(output::format_json("{not json}", false), None);
+    assert_eq!(output::format_json("42", false), None);
+}
@@ -0,0 +1,36 @@
+use ola::utils::json_repair::repair_json;
+use serde_json::json;
+
+#[test]
+fn test_repair_prose_wrapped_json_with_trailing_comma() {
+    let response = r#"Sure! Here is the data you asked for:
+
+{
+    "name": "ola",
+    "tags": ["cli", "llm",],
+    "stars": 42,
+}
+
+Let me know if you need anything else."#;
+
+    let value = repair_json(response).expect("repair should succeed");
+    assert_eq!(value, json!({ "name": "ola", "tags": ["cli", "llm"], "stars": 42 }));
+}
+
+#[test]
+fn test_repair_fenced_json_after_thinking() {
+    let response = "<think>Maybe {something}?</think>\n```json\n[{\"a\": \"}\"}, {\"b\": 2},]\n```";
+    let value = repair_json(response).expect("repair should succeed");
+    assert_eq!(value, json!([{ "a": "}" }, { "b": 2 }]));
+}
+
+#[test]
+fn test_repair_balances_truncated_json() {
+    let value = repair_json(r#"{"items": [1, 2, {"k": "v"#).expect("repair should succeed");
+    assert_eq!(value, json!({ "items": [1, 2, { "k": "v" }] }));
+}
+
+#[test]
+fn test_repair_fails_without_json() {
+    assert!(repair_json("No structured output here.").is_err());
+}
@@ -0,0 +1,60 @@
+use chrono::NaiveDate;
+use ola::logs;
+use std::fs;
+use tempfile::tempdir;
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+// Midday UTC keeps each entry on the same date in any local timezone
+const LOG: &str = concat!(
+    r#"{"timestamp":"2024-05-01T12:00:00+00:00","model":"gpt-4o","output_length":100}"#, "\n",
+    r#"{"timestamp":"2024-05-01T12:30:00+00:00","model":"llama3","output_length":50}"#, "\n",
+    "not json\n",
+    r#"{"timestamp":"2024-05-02T12:00:00+00:00","model":"gpt-4o","output_length":20,"tokens":8,"cost":0.001}"#, "\n",
+    r#"{"timestamp":"2024-05-02T12:05:00+00:00","model":"gpt-4o","output_length":30,"tokens":12}"#, "\n",
+    r#"{"timestamp":"2024-05-02T12:10:00+00:00","model":"gpt-4o","output_length":40}"#, "\n",
+);
+
+#[test]
+fn test_summary_groups_sessions_by_day() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().join("sessions.jsonl");
+    fs::write(&path, LOG).unwrap();
+
+    let entries = logs::read_entries(&path).unwrap();
+    assert_eq!(entries.len(), 5);
+
+    let days = logs::summarize_by_day(&entries, None);
+    assert_eq!(days.len(), 2);
+    assert_eq!(days[0].date, date(2024, 5, 1));
+    assert_eq!(days[0].sessions, 2);
+    assert_eq!(days[0].models.get("gpt-4o"), Some(&1));
+    assert_eq!(days[0].models.get("llama3"), Some(&1));
+    assert_eq!(days[0].output_length, 150);
+    assert_eq!(days[0].tokens, None);
+    assert_eq!(days[1].date, date(2024, 5, 2));
+    assert_eq!(days[1].sessions, 3);
+    assert_eq!(days[1].output_length, 90);
+    assert_eq!(days[1].tokens, Some(20));
+    assert_eq!(days[1].cost, Some(0.001));
+
+    // --since leaves out earlier days
+    let since = logs::parse_since("2024-05-02", date(2024, 6, 1)).unwrap();
+    let days = logs::summarize_by_day(&entries, Some(since));
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].sessions, 3);
+
+    // A missing log is empty rather than an error
+    assert!(logs::read_entries(&temp_dir.path().join("missing.jsonl")).unwrap().is_empty());
+}
+
+#[test]
+fn test_parse_since() {
+    let today = date(2024, 5, 10);
+    assert_eq!(logs::parse_since("7d", today).unwrap(), date(2024, 5, 3));
+    assert_eq!(logs::parse_since("0d", today).unwrap(), today);
+    assert_eq!(logs::parse_since("2024-01-31", today).unwrap(), date(2024, 1, 31));
+    assert!(logs::parse_since("last week", today).is_err());
+}
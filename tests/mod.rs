@@ -1,5 +1,7 @@
 // Main test module for the ola crate
-mod common;
+
+// Each test file is also its own target, so the ones that use tests/common include it themselves
+#![allow(clippy::duplicate_mod)]
 
 // Integration tests for CLI functionality
 mod cli_main_test;
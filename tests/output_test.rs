@@ -0,0 +1,26 @@
+use ola::utils::output::StreamProgress;
+
+#[test]
+fn test_stream_progress_renders_and_clears() {
+    // 40 characters estimate to 10 tokens, so each 8-character chunk is 20% of max_tokens
+    let mut progress = StreamProgress::new(Vec::new(), 10);
+    for chunk in ["The tide", " comes i", "n twice ", "a day, m", "ostly..."] {
+        progress.update(chunk).unwrap();
+    }
+    let out = String::from_utf8(progress.finish().unwrap()).unwrap();
+
+    let frames: Vec<&str> = out.split('\r').filter(|frame| !frame.is_empty()).collect();
+    assert_eq!(frames.len(), 6);
+    assert!(frames[0].contains(" 20%"));
+    assert!(frames[4].contains(" 100%"));
+    assert_eq!(frames[5], "\x1B[K");
+}
+
+#[test]
+fn test_stream_progress_caps_at_max_tokens() {
+    let mut progress = StreamProgress::new(Vec::new(), 2);
+    progress.update("a response well past the requested max_tokens").unwrap();
+    let out = String::from_utf8(progress.finish().unwrap()).unwrap();
+    assert!(out.contains(" 100%"));
+    assert!(!out.contains('░'));
+}
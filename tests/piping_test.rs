@@ -0,0 +1,15 @@
+use ola::utils::piping;
+use std::time::Duration;
+
+#[cfg(unix)]
+#[test]
+fn test_context_command_output_over_the_cap_is_truncated() {
+    // seq writes far more than the cap; the command must still exit cleanly
+    let text = piping::run_context_command("seq 1 20000", Duration::from_secs(10), 100).unwrap();
+    assert!(text.starts_with("1\n2\n3\n"));
+    assert!(text.ends_with("[Output truncated at 100 bytes]"));
+
+    // The same holds for a command that is chatty on stderr
+    let text = piping::run_context_command("seq 1 20000 >&2; echo done", Duration::from_secs(10), 100).unwrap();
+    assert_eq!(text.trim(), "done");
+}
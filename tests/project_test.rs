@@ -55,7 +55,7 @@ fn test_project_manager_create_project() -> Result<(), Box<dyn std::error::Error
     std::env::set_var("HOME", temp_dir.path());
     
     let project_manager = ProjectManager::new()?;
-    let project = project_manager.create_project("Test Project".to_string())?;
+    let project = project_manager.create_project("Test Project".to_string(), None)?;
     
     assert_eq!(project.name, "Test Project");
     assert!(!project.id.is_empty());
@@ -82,7 +82,7 @@ fn test_project_manager_save_and_load() -> Result<(), Box<dyn std::error::Error>
     std::env::set_var("HOME", temp_dir.path());
     
     let project_manager = ProjectManager::new()?;
-    let mut project = project_manager.create_project("Test Project".to_string())?;
+    let mut project = project_manager.create_project("Test Project".to_string(), None)?;
     
     // Add some data to the project
     let goal = Goal::new("Test Goal".to_string(), 0);
@@ -114,7 +114,7 @@ fn test_project_manager_upload_file() -> Result<(), Box<dyn std::error::Error>>
     std::env::set_var("HOME", temp_dir.path());
     
     let project_manager = ProjectManager::new()?;
-    let project = project_manager.create_project("Test Project".to_string())?;
+    let project = project_manager.create_project("Test Project".to_string(), None)?;
     
     // Upload a test file
     let file_content = b"fn main() { println!(\"Hello, world!\"); }";
@@ -136,6 +136,35 @@ fn test_project_manager_upload_file() -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+#[test]
+fn test_project_manager_upload_compressed_file() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    std::env::set_var("HOME", temp_dir.path());
+    
+    let project_manager = ProjectManager::new()?.with_compression(true);
+    let project = project_manager.create_project("Compressed Project".to_string(), None)?;
+    
+    let file_content = "The quick brown fox jumps over the lazy dog.\n".repeat(200);
+    let file_obj = project_manager.upload_file(&project.id, "notes.txt".to_string(), file_content.as_bytes())?;
+    assert!(file_obj.compressed);
+    assert_eq!(file_obj.size, file_content.len() as u64);
+    
+    // Stored as <id>.gz and smaller than the original
+    let stored = temp_dir.path().join(".ola/data/projects").join(&project.id)
+        .join("files").join(format!("{}.gz", file_obj.id));
+    assert!(std::fs::metadata(&stored)?.len() < file_content.len() as u64);
+    
+    let downloaded = project_manager.download_file(&project.id, &file_obj.id)?;
+    assert_eq!(downloaded.unwrap(), file_content.as_bytes());
+    let text = project_manager.read_file_as_text(&project.id, &file_obj.id)?;
+    assert_eq!(text.unwrap(), file_content);
+    
+    assert!(project_manager.delete_file(&project.id, &file_obj.id)?);
+    assert!(!stored.exists());
+    
+    Ok(())
+}
+
 #[test]
 fn test_project_manager_list_projects() -> Result<(), Box<dyn std::error::Error>> {
     // Use temporary directory for testing
@@ -149,8 +178,8 @@ fn test_project_manager_list_projects() -> Result<(), Box<dyn std::error::Error>
     assert_eq!(projects.len(), 0);
     
     // Create a project
-    let _project1 = project_manager.create_project("Project 1".to_string())?;
-    let _project2 = project_manager.create_project("Project 2".to_string())?;
+    let _project1 = project_manager.create_project("Project 1".to_string(), None)?;
+    let _project2 = project_manager.create_project("Project 2".to_string(), None)?;
     
     // List projects
     let projects = project_manager.list_projects()?;
@@ -161,4 +190,203 @@ fn test_project_manager_list_projects() -> Result<(), Box<dyn std::error::Error>
     assert_eq!(projects[1].name, "Project 1");
     
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn test_project_manager_purge_projects() -> Result<(), Box<dyn std::error::Error>> {
+    // Use temporary directory for testing
+    let temp_dir = TempDir::new()?;
+    std::env::set_var("HOME", temp_dir.path());
+    
+    let project_manager = ProjectManager::new()?;
+    let project = project_manager.create_project("Project 1".to_string(), None)?;
+    project_manager.create_project("Project 2".to_string(), None)?;
+    project_manager.create_project("Project 3".to_string(), None)?;
+    project_manager.set_active_project(&project.id)?;
+    
+    let removed = project_manager.purge_projects(false)?;
+    assert_eq!(removed, 3);
+    assert!(project_manager.list_projects()?.is_empty());
+    assert!(project_manager.get_active_project()?.is_none());
+    
+    Ok(())
+}
+
+#[test]
+fn test_project_manager_purge_keeps_default() -> Result<(), Box<dyn std::error::Error>> {
+    // Use temporary directory for testing
+    let temp_dir = TempDir::new()?;
+    std::env::set_var("HOME", temp_dir.path());
+    
+    let project_manager = ProjectManager::new()?;
+    project_manager.get_default_project()?;
+    project_manager.create_project("Project 1".to_string(), None)?;
+    project_manager.create_project("Project 2".to_string(), None)?;
+    
+    let removed = project_manager.purge_projects(true)?;
+    assert_eq!(removed, 2);
+    
+    let projects = project_manager.list_projects()?;
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].id, "default");
+    
+    Ok(())
+}
+
+#[test]
+fn test_project_import_goals_from_file() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("goals.md");
+    std::fs::write(&path, "# Goals\n- Write the parser\n\n2. Add tests\nShip it\n")?;
+
+    let items = ola::models::parse_import_items(&std::fs::read_to_string(&path)?);
+    let mut project = Project::new("Test Project".to_string());
+    let added = project.import_goals(&items);
+
+    assert_eq!(added, 3);
+    let goals: Vec<(&str, u32)> = project.goals.iter().map(|g| (g.text.as_str(), g.order)).collect();
+    assert_eq!(goals, vec![("Write the parser", 0), ("Add tests", 1), ("Ship it", 2)]);
+    Ok(())
+}
+
+#[test]
+fn test_project_transaction_writes_once_on_commit() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    std::env::set_var("HOME", temp_dir.path());
+
+    let project_manager = ProjectManager::new()?;
+    let project = project_manager.create_project("Batch Project".to_string(), None)?;
+    let writes = project_manager.writes();
+
+    let goals: String = (0..100).map(|i| format!("- Goal {}\n", i)).collect();
+    let items = ola::models::parse_import_items(&goals);
+    let mut transaction = project_manager.begin(project);
+    assert_eq!(transaction.project_mut().import_goals(&items), 100);
+    transaction.project_mut().add_context(Context::new("Shared context".to_string(), 0));
+
+    // Nothing is written before the commit, and the commit writes once
+    assert_eq!(project_manager.writes(), writes);
+    let committed = transaction.commit()?;
+    assert_eq!(project_manager.writes(), writes + 1);
+
+    let loaded = project_manager.load_project(&committed.id)?.unwrap();
+    assert_eq!(loaded.goals.len(), 100);
+    assert_eq!(loaded.goals[99].text, "Goal 99");
+    assert_eq!(loaded.contexts.len(), 1);
+
+    // Dropping a transaction discards its edits
+    let mut discarded = project_manager.begin(loaded);
+    discarded.project_mut().add_goal(Goal::new("Never saved".to_string(), 100));
+    drop(discarded);
+    assert_eq!(project_manager.writes(), writes + 1);
+    assert_eq!(project_manager.load_project(&committed.id)?.unwrap().goals.len(), 100);
+    Ok(())
+}
+
+#[test]
+fn test_project_manager_create_with_explicit_id() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    std::env::set_var("HOME", temp_dir.path());
+
+    let project_manager = ProjectManager::new()?;
+    let project = project_manager.create_project("Docs Example".to_string(), Some("docs-example"))?;
+    assert_eq!(project.id, "docs-example");
+
+    let loaded = project_manager.load_project("docs-example")?.expect("project should load by explicit id");
+    assert_eq!(loaded.name, "Docs Example");
+
+    // Collisions, reserved ids, and unsafe ids are rejected
+    assert!(project_manager.create_project("Again".to_string(), Some("docs-example")).is_err());
+    assert!(project_manager.create_project("Reserved".to_string(), Some("default")).is_err());
+    assert!(project_manager.create_project("Escape".to_string(), Some("../outside")).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_project_manager_merge_projects() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    std::env::set_var("HOME", temp_dir.path());
+
+    let project_manager = ProjectManager::new()?;
+    let mut source = project_manager.create_project("Source".to_string(), None)?;
+    let mut target = project_manager.create_project("Target".to_string(), None)?;
+
+    source.add_goal(Goal::new("Shared goal".to_string(), 0));
+    source.add_goal(Goal::new("Source goal".to_string(), 1));
+    let source_file = project_manager.upload_file(&source.id, "notes.md".to_string(), b"# Notes")?;
+    source.add_file(source_file.clone());
+    project_manager.save_project(&source)?;
+
+    target.add_goal(Goal::new("Shared goal".to_string(), 0));
+    let target_file = project_manager.upload_file(&target.id, "main.rs".to_string(), b"fn main() {}")?;
+    target.add_file(target_file);
+    project_manager.save_project(&target)?;
+
+    let summary = project_manager.merge_projects(&source.id, &target.id, true)?;
+    assert_eq!((summary.goals, summary.files), (1, 1));
+
+    let merged = project_manager.load_project(&target.id)?.unwrap();
+    let goals: Vec<&str> = merged.goals.iter().map(|g| g.text.as_str()).collect();
+    assert_eq!(goals, vec!["Shared goal", "Source goal"]);
+    let filenames: Vec<&str> = merged.files.iter().map(|f| f.filename.as_str()).collect();
+    assert_eq!(filenames, vec!["main.rs", "notes.md"]);
+
+    // The copied file has a new id and its own bytes in the target
+    let copied = merged.files.iter().find(|f| f.filename == "notes.md").unwrap();
+    assert_ne!(copied.id, source_file.id);
+    assert_eq!(project_manager.download_file(&target.id, &copied.id)?.unwrap(), b"# Notes");
+
+    Ok(())
+}
+
+#[test]
+fn test_read_file_as_text_drops_bom_and_crlf() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    std::env::set_var("HOME", temp_dir.path());
+
+    let project_manager = ProjectManager::new()?;
+    let project = project_manager.create_project("Windows".to_string(), None)?;
+    let raw = b"\xEF\xBB\xBFline one\r\nline two\r\n";
+    let file = project_manager.upload_file(&project.id, "notes.txt".to_string(), raw)?;
+
+    let text = project_manager.read_file_as_text(&project.id, &file.id)?.unwrap();
+    assert_eq!(text, "line one\nline two\n");
+
+    // Line endings can be kept, but the BOM is always dropped
+    let text = project_manager.with_keep_crlf(true).read_file_as_text(&project.id, &file.id)?.unwrap();
+    assert_eq!(text, "line one\r\nline two\r\n");
+
+    // Downloads keep the original bytes
+    let project_manager = ProjectManager::new()?;
+    assert_eq!(project_manager.download_file(&project.id, &file.id)?.unwrap(), raw);
+
+    Ok(())
+}
+
+#[test]
+fn test_files_to_evict_follows_policy() {
+    use chrono::{Duration, Utc};
+    use ola::project::files_to_evict;
+    use ola::settings::EvictionPolicy;
+
+    let now = Utc::now();
+    let mut oldest = ProjectFile::new("oldest.md".to_string(), 300, None);
+    oldest.uploaded_at = now - Duration::hours(2);
+    let mut largest = ProjectFile::new("largest.rs".to_string(), 900, None);
+    largest.uploaded_at = now - Duration::hours(1);
+    let mut newest = ProjectFile::new("newest.txt".to_string(), 400, None);
+    newest.uploaded_at = now;
+    let files = vec![oldest, largest, newest];
+
+    let names = |policy| -> Vec<String> {
+        files_to_evict(&files, 1000, policy).iter().map(|f| f.filename.clone()).collect()
+    };
+
+    // 1600 bytes total against a 1000-byte budget
+    assert_eq!(names(EvictionPolicy::Oldest), vec!["oldest.md", "largest.rs"]);
+    assert_eq!(names(EvictionPolicy::Largest), vec!["largest.rs"]);
+    assert_eq!(names(EvictionPolicy::Newest), vec!["newest.txt", "largest.rs"]);
+
+    // No budget means nothing is dropped
+    assert!(files_to_evict(&files, 0, EvictionPolicy::Oldest).is_empty());
+}
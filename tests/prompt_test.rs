@@ -3,7 +3,13 @@ use std::io::Write;
 use mockito::{Server, Mock};
 use tempfile::{tempdir, TempDir};
 use std::env;
-use ola::prompt::{self, PromptResult};
+use ola::prompt::{self, PromptOptions, PromptResult};
+use ola::settings::ContextWindowCheck;
+
+#[path = "common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+use common::TestHome;
 
 // Create a mock for OpenAI API
 fn mock_openai_api() -> Mock {
@@ -280,4 +286,322 @@ fn test_with_thinking_hidden() {
     if let Some(home) = old_home {
         env::set_var("HOME", home);
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_format_file_becomes_return_format() {
+    let temp_dir = tempdir().unwrap();
+    let format_path = temp_dir.path().join("format.md");
+    fs::write(&format_path, "A JSON object with keys `summary` and `steps`\n").unwrap();
+
+    let format = prompt::read_spec_file(&format_path).unwrap();
+    assert_eq!(format, "A JSON object with keys `summary` and `steps`");
+
+    let settings = ola::settings::Settings::default();
+    let assembled = prompt::build_prompt(&settings, "Plan a release", &format, "", None, &PromptOptions::default()).unwrap();
+    let expected = format!("{}{}", settings.prompt_template.return_format_prefix, format);
+    assert!(assembled.contains(&expected));
+}
+
+#[test]
+fn test_default_warnings_used_without_flag() {
+    let settings: ola::settings::Settings = serde_yaml::from_str("defaults:\n  warnings: Cite sources.\n").unwrap();
+
+    let warnings = prompt::warnings_or_default(&settings, None);
+    let assembled = prompt::build_prompt(&settings, "Explain tides", "text", warnings, None, &PromptOptions::default()).unwrap();
+    assert!(assembled.contains("Cite sources."));
+
+    // An explicit value still wins over the default
+    assert_eq!(prompt::warnings_or_default(&settings, Some("Be brief.")), "Be brief.");
+}
+
+#[test]
+fn test_fallback_model_used_after_retryable_failure() {
+    let mut server = Server::new();
+    let primary = server.mock("POST", "/api/generate")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "model": "primary-model" })))
+        .with_status(503)
+        .with_body("model overloaded")
+        .create();
+    let fallback = server.mock("POST", "/api/generate")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "model": "fallback-model" })))
+        .with_header("content-type", "application/x-ndjson")
+        .with_body("{\"response\":\"Answer from fallback\",\"done\":true}\n")
+        .create();
+
+    let no_retries = ola::api::RequestOptions { max_retries: Some(0), ..Default::default() };
+    let api_client = ola::ApiClient::with_options("Ollama", "", Some(&server.url()), &no_retries).unwrap();
+    let result = prompt::stream_with_fallback(
+        &api_client,
+        "Test prompt",
+        "primary-model",
+        &["fallback-model".to_string()],
+        false,
+        false,
+        true,
+        ContextWindowCheck::Warn,
+    ).unwrap();
+
+    primary.assert();
+    fallback.assert();
+    assert_eq!(result.content, "Answer from fallback");
+    assert_eq!(result.model, "fallback-model");
+}
+
+#[test]
+fn test_no_fallback_on_non_retryable_failure() {
+    let mut server = Server::new();
+    let _primary = server.mock("POST", "/api/generate")
+        .with_status(400)
+        .with_body("bad request")
+        .create();
+
+    let api_client = ola::ApiClient::new("Ollama", "", Some(&server.url())).unwrap();
+    let result = prompt::stream_with_fallback(
+        &api_client,
+        "Test prompt",
+        "primary-model",
+        &["fallback-model".to_string()],
+        false,
+        false,
+        true,
+        ContextWindowCheck::Warn,
+    );
+
+    let error = result.unwrap_err();
+    let api_error = error.downcast_ref::<ola::api::ApiError>().expect("expected an ApiError");
+    assert_eq!(api_error.status, 400);
+}
+
+#[test]
+fn test_no_thinking_passes_openai_response_through() {
+    let mut server = Server::new();
+    let _m = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"choices":[{"message":{"content":"Use <think> tags to mark reasoning."},"finish_reason":"stop"}]}"#)
+        .create();
+
+    let api_client = ola::ApiClient::new("OpenAI", "test-key", Some(&server.url())).unwrap();
+    let result = prompt::stream_with_fallback(&api_client, "Test prompt", "o3", &[], true, false, true, ContextWindowCheck::Warn).unwrap();
+
+    // OpenAI keeps its reasoning hidden, so the visible answer comes through unchanged
+    assert_eq!(result.content, "Use <think> tags to mark reasoning.");
+}
+
+#[test]
+fn test_no_thinking_strips_openai_compatible_think_blocks() {
+    let mut server = Server::new();
+    let _m = server.mock("POST", "/v1/chat/completions")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"choices":[{"message":{"content":"<think>Recall the moon.</think>\n\nHigh tide is at noon."},"finish_reason":"stop"}]}"#)
+        .create();
+
+    // An OpenAI-compatible endpoint may serve a reasoning model that emits <think> blocks
+    let api_client = ola::ApiClient::new("OpenAI", "test-key", Some(&server.url())).unwrap();
+    let result = prompt::stream_with_fallback(&api_client, "Test prompt", "o3", &[], true, false, true, ContextWindowCheck::Warn).unwrap();
+
+    assert_eq!(result.content, "High tide is at noon.");
+    assert!(result.raw.contains("<think>Recall the moon.</think>"));
+}
+
+#[test]
+fn test_anthropic_thinking_blocks_are_captured_and_filtered() {
+    let mut server = Server::new();
+    let mock = server.mock("POST", "/v1/messages")
+        .match_header("anthropic-beta", mockito::Matcher::Missing)
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "thinking": { "type": "enabled" } })))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"content":[{"type":"thinking","thinking":"Tides follow\nthe moon.","signature":"sig"},{"type":"text","text":"High tide is at noon."}],"stop_reason":"end_turn"}"#)
+        .expect(2)
+        .create();
+
+    let options = ola::api::RequestOptions { extended_thinking: true, ..Default::default() };
+    let api_client = ola::ApiClient::with_options("Anthropic", "test-key", Some(&server.url()), &options).unwrap();
+
+    // The reasoning is kept as a <think> block...
+    let result = prompt::stream_with_fallback(&api_client, "Test prompt", "claude-sonnet-4-20250514", &[], false, true, false, ContextWindowCheck::Off).unwrap();
+    assert_eq!(result.content, "<think>Tides follow\nthe moon.</think>\n\nHigh tide is at noon.");
+
+    // ...that --no-thinking removes
+    let result = prompt::stream_with_fallback(&api_client, "Test prompt", "claude-sonnet-4-20250514", &[], true, true, false, ContextWindowCheck::Off).unwrap();
+    assert_eq!(result.content.trim(), "High tide is at noon.");
+    assert!(result.raw.contains("<think>Tides follow"));
+    mock.assert();
+}
+
+#[test]
+fn test_no_thinking_strips_ollama_think_blocks() {
+    let mut server = Server::new();
+    let _m = server.mock("POST", "/api/generate")
+        .with_header("content-type", "application/x-ndjson")
+        .with_body("{\"response\":\"<think>reasoning</think>Answer\",\"done\":true}\n")
+        .create();
+
+    let api_client = ola::ApiClient::new("Ollama", "", Some(&server.url())).unwrap();
+    let result = prompt::stream_with_fallback(&api_client, "Test prompt", "deepseek-r1", &[], true, true, true, ContextWindowCheck::Off).unwrap();
+    assert_eq!(result.content, "Answer");
+}
+
+#[test]
+fn test_strip_thinking_blocks_spans_lines() {
+    let response = "<think>\nFirst, recall the tides.\n\nThen check the moon.\n</think>\n\nHigh tide is at noon.";
+    assert_eq!(prompt::strip_thinking_blocks(response).unwrap(), "High tide is at noon.");
+}
+
+#[test]
+fn test_custom_context_and_request_labels() {
+    let mut template = ola::settings::PromptTemplate::default();
+    assert_eq!(template.context_label, "Context:");
+    assert_eq!(template.user_request_label, "## User Request");
+
+    template.context_label = "<context>".to_string();
+    template.user_request_label = "### Task".to_string();
+
+    let formatted = ola::api::format_prompt_with_template(&template, "Summarize", "text", "", Some("piped text"));
+    assert!(formatted.ends_with("\n---\n<context>\npiped text\n---"));
+
+    let mut project = ola::Project::new("Labels".to_string());
+    project.add_goal(ola::Goal::new("Ship it".to_string(), 0));
+    let project_prompt = prompt::build_project_prompt(&project, Some("Review"), &template, &Default::default()).unwrap();
+    assert!(project_prompt.contains("### Task\nReview"));
+    assert!(!project_prompt.contains("## User Request"));
+}
+
+#[test]
+fn test_section_prefixes_are_opt_in() {
+    // Without a prompt_template the sections carry no prefixes
+    let settings: ola::Settings = serde_yaml::from_str("behavior:\n  enable_logging: false\n").unwrap();
+    let formatted = ola::api::format_prompt_with_template(&settings.prompt_template, "Summarize", "text", "Be brief", None);
+    assert_eq!(formatted, "Summarize\ntext\nBe brief");
+
+    // Setting only the labels doesn't add them either
+    let settings: ola::Settings = serde_yaml::from_str("prompt_template:\n  context_label: \"<context>\"\n").unwrap();
+    assert_eq!(settings.prompt_template.goals_prefix, "");
+
+    let settings: ola::Settings = serde_yaml::from_str("prompt_template:\n  goals_prefix: \"Goals: \"\n").unwrap();
+    let formatted = ola::api::format_prompt_with_template(&settings.prompt_template, "Summarize", "text", "Be brief", None);
+    assert_eq!(formatted, "Goals: Summarize\ntext\nBe brief");
+}
+
+#[test]
+fn test_context_is_a_delimited_block() {
+    let mut template = ola::settings::PromptTemplate::default();
+    let formatted = ola::api::format_prompt_with_template(&template, "Summarize", "text", "Be brief", Some("line one\nline two"));
+    // A blank line separates the warnings from the block, which is fenced by separator lines
+    assert!(formatted.ends_with("Be brief\n\n---\nContext:\nline one\nline two\n---"));
+
+    // No separator leaves only the blank line and the heading
+    template.context_separator = String::new();
+    let formatted = ola::api::format_prompt_with_template(&template, "Summarize", "text", "Be brief", Some("piped"));
+    assert!(formatted.ends_with("Be brief\n\nContext:\npiped"));
+
+    // Labels from older settings files that end in a space still get their own line
+    template.context_label = "Context: ".to_string();
+    template.context_separator = "===".to_string();
+    let block = ola::api::format_context_block(&template, &template.context_label, "piped");
+    assert_eq!(block, "\n\n===\nContext:\npiped\n===");
+}
+
+#[test]
+fn test_response_language_instruction() {
+    let settings = ola::settings::Settings::default();
+    let in_language = |language: &str| PromptOptions { language: Some(language.to_string()), ..Default::default() };
+
+    let assembled = prompt::build_prompt(&settings, "Explain tides", "text", "", None, &in_language("fr")).unwrap();
+    assert!(assembled.ends_with("\nRespond in French."));
+
+    let assembled = prompt::build_prompt(&settings, "Explain tides", "text", "", None, &in_language("none")).unwrap();
+    assert!(!assembled.contains("Respond in"));
+
+    // Unknown codes are passed through as the language name
+    assert_eq!(prompt::language_name("Swahili").as_deref(), Some("Swahili"));
+}
+
+#[test]
+fn test_response_similarity() {
+    assert_eq!(prompt::response_similarity("Same  answer\n", "same answer"), 1.0);
+    assert!(prompt::response_similarity("The sky is blue today", "The sky is blue tonight") > 0.5);
+    assert!(prompt::response_similarity("The sky is blue", "Cats sleep all day") < 0.5);
+}
+
+#[test]
+fn test_benchmark_summary_from_samples() {
+    use std::time::Duration;
+
+    let samples: Vec<(Duration, usize)> = (1..=20).map(|ms| (Duration::from_millis(ms * 100), 400)).collect();
+    let summary = prompt::BenchmarkSummary::from_samples(&samples).unwrap();
+    assert_eq!(summary.runs, 20);
+    assert_eq!(summary.min, Duration::from_millis(100));
+    assert_eq!(summary.max, Duration::from_millis(2000));
+    assert_eq!(summary.mean, Duration::from_millis(1050));
+    assert_eq!(summary.p95, Duration::from_millis(1900));
+    // 20 responses of ~100 tokens over 21 seconds
+    assert!((summary.tokens_per_sec - 2000.0 / 21.0).abs() < 0.01);
+
+    assert!(prompt::BenchmarkSummary::from_samples(&[]).is_none());
+}
+
+#[test]
+fn test_benchmark_without_runs_is_an_error() {
+    let server = Server::new();
+    let temp_dir = TestHome::openai(&server.url()).create();
+    env::set_var("HOME", temp_dir.path());
+
+    let options = prompt::PromptOptions { quiet: true, ..Default::default() };
+    let err = prompt::benchmark("Say hello", "text", "", &options, 0).unwrap_err();
+    assert!(err.to_string().contains("at least one run"));
+}
+
+#[test]
+fn test_selected_provider_used_for_run() {
+    let mut openai = Server::new();
+    let mut ollama = Server::new();
+    let active_mock = openai.mock("POST", "/v1/chat/completions").expect(0).create();
+    let chosen_mock = ollama.mock("POST", "/api/generate")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "model": "llama3" })))
+        .with_header("content-type", "application/x-ndjson")
+        .with_body("{\"response\":\"Answer from Ollama\",\"done\":true}\n")
+        .create();
+
+    let temp_dir = TestHome::openai(&openai.url())
+        .provider("Ollama", Some("llama3"), &ollama.url())
+        .create();
+    env::set_var("HOME", temp_dir.path());
+
+    // Script the selector: the active provider is offered first, then pick Ollama's configured model
+    let config = ola::config::Config::load().unwrap();
+    let choices = config.model_choices();
+    assert_eq!(choices[0], ("OpenAI".to_string(), "gpt-4o".to_string()));
+    let (provider, model) = choices.iter()
+        .find(|(provider, _)| provider == "Ollama")
+        .cloned()
+        .unwrap();
+    assert_eq!(model, "llama3");
+
+    let options = prompt::PromptOptions {
+        quiet: true,
+        provider: Some(provider),
+        model: Some(model),
+        ..Default::default()
+    };
+    let response = prompt::structure_reasoning("Explain tides", "text", "", None, &options).unwrap();
+
+    assert_eq!(response, "Answer from Ollama");
+    chosen_mock.assert();
+    active_mock.assert();
+
+    // The active provider is unchanged
+    assert_eq!(ola::config::Config::load().unwrap().active_provider, "OpenAI");
+}
+
+#[test]
+fn test_parse_structured_hints() {
+    let hints = prompt::parse_hints("Keep answers short.\n\n[Goals]\nReview the diff\n[Return Format] markdown\n[Warnings]\nNo speculation.\nCite files.\n");
+    assert_eq!(hints.goals.as_deref(), Some("Review the diff"));
+    assert_eq!(hints.return_format.as_deref(), Some("markdown"));
+    assert_eq!(hints.warnings.as_deref(), Some("No speculation.\nCite files."));
+    assert_eq!(hints.notes, "Keep answers short.");
+
+    // Free-form hints are kept verbatim
+    let free_form = prompt::parse_hints("Be concise. [Not a section]\n");
+    assert_eq!(free_form, prompt::SessionHints { notes: "Be concise. [Not a section]\n".to_string(), ..Default::default() });
+}
@@ -2,7 +2,7 @@ use std::fs::{self, File};
 use std::io::Write;
 use tempfile::{tempdir, TempDir};
 use std::env;
-use ola::settings::{Settings, DefaultSettings, BehaviorSettings};
+use ola::settings::{Settings, DefaultSettings, BehaviorSettings, NvimSettings};
 
 // Create a temporary settings file
 fn setup_temp_settings() -> TempDir {
@@ -78,18 +78,40 @@ fn test_settings_save() {
     // Create custom settings
     let settings = Settings {
         default_model: "custom_model".to_string(),
+        fallback_models: vec!["backup_model".to_string()],
         prompt_template: Default::default(),
         defaults: DefaultSettings {
             return_format: "json".to_string(),
+            warnings: "Cite sources.".to_string(),
+            response_language: String::new(),
+            reasoning_effort: String::new(),
             quiet: true,
             no_thinking: true,
             clipboard: true,
+            collapse_whitespace: false,
+            temperature: None,
+            max_tokens: None,
         },
         behavior: BehaviorSettings {
             enable_logging: true,
             log_file: "custom.log".to_string(),
+            log_raw_response: false,
+            convergence_threshold: 0.0,
             thinking_animation: Default::default(),
+            theme: Default::default(),
+            emoji: None,
+            context_window_check: Default::default(),
+            max_stdin_bytes: 0,
+            command_timeout_secs: 0,
+            command_max_output_bytes: 0,
+            max_request_bytes: 0,
+            request_timeout_secs: 120,
+            nvim: Default::default(),
         },
+        error_helper: Default::default(),
+        project_context: Default::default(),
+        env_provider_priority: vec!["OpenAI".to_string()],
+        redact_patterns: Vec::new(),
     };
     
     // Save the settings
@@ -136,4 +158,55 @@ fn test_settings_missing_file() {
     if let Some(home) = old_home {
         env::set_var("HOME", home);
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_edit_with_invalid_yaml_restores_backup() {
+    let temp_dir = tempdir().unwrap();
+    let settings_file = temp_dir.path().join("settings.yaml");
+    let original = "default_model: \"before_edit\"\n";
+    fs::write(&settings_file, original).unwrap();
+
+    // Fake editor that leaves behind broken YAML
+    let result = Settings::edit_file_with(&settings_file, |path| {
+        fs::write(path, "default_model: [unclosed\n")
+    });
+
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("| default_model: [unclosed"));
+    assert_eq!(fs::read_to_string(&settings_file).unwrap(), original);
+    assert!(!settings_file.with_extension("yaml.edit.bak").exists());
+}
+
+#[test]
+fn test_edit_with_valid_yaml_keeps_changes() {
+    let temp_dir = tempdir().unwrap();
+    let settings_file = temp_dir.path().join("settings.yaml");
+    fs::write(&settings_file, "default_model: \"before_edit\"\n").unwrap();
+
+    let settings = Settings::edit_file_with(&settings_file, |path| {
+        fs::write(path, "default_model: \"after_edit\"\n")
+    }).unwrap();
+
+    assert_eq!(settings.default_model, "after_edit");
+    assert!(!settings_file.with_extension("yaml.edit.bak").exists());
+}
+
+#[test]
+fn test_nvim_settings_default_when_missing_and_round_trip() {
+    // A settings file written before the nvim key existed
+    let settings: Settings = serde_yaml::from_str("behavior:\n  enable_logging: false\n").unwrap();
+    assert_eq!(settings.behavior.nvim, NvimSettings { enabled: false, path: "nvim".to_string(), args: Vec::new() });
+
+    let temp_dir = tempdir().unwrap();
+    let settings_file = temp_dir.path().join("settings.yaml");
+    fs::write(&settings_file, "behavior:\n  nvim:\n    enabled: true\n").unwrap();
+    let mut settings = Settings::edit_file_with(&settings_file, |_| Ok(())).unwrap();
+    assert!(settings.behavior.nvim.enabled);
+    assert_eq!(settings.behavior.nvim.path, "nvim");
+
+    settings.behavior.nvim.args = vec!["--clean".to_string()];
+    fs::write(&settings_file, serde_yaml::to_string(&settings).unwrap()).unwrap();
+    let loaded = Settings::edit_file_with(&settings_file, |_| Ok(())).unwrap();
+    assert_eq!(loaded.behavior.nvim, settings.behavior.nvim);
+}